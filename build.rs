@@ -31,8 +31,34 @@ fn main() {
 
         // 创建EGL绑定文件
         let mut file = File::create(&dest.join("egl_bindings.rs")).unwrap();
-        // 生成OpenGL ES 1.5 Core API的绑定
-        let registry = Registry::new(Api::Egl, (1, 5), Profile::Core, Fallbacks::All, []);
+        // 生成EGL 1.5 Core API及所用扩展的绑定
+        let registry = Registry::new(
+            Api::Egl,
+            (1, 5),
+            Profile::Core,
+            Fallbacks::All,
+            [
+                "EGL_EXT_platform_base",
+                "EGL_EXT_platform_device",
+                "EGL_EXT_device_base",
+                "EGL_EXT_device_enumeration",
+                "EGL_EXT_device_query",
+                "EGL_EXT_device_drm",
+                "EGL_MESA_platform_surfaceless",
+                "EGL_KHR_platform_gbm",
+                "EGL_KHR_platform_x11",
+                "EGL_KHR_platform_wayland",
+                "EGL_KHR_fence_sync",
+                "EGL_KHR_wait_sync",
+                "EGL_ANDROID_native_fence_sync",
+                "EGL_KHR_image_base",
+                "EGL_KHR_gl_texture_2D_image",
+                "EGL_KHR_gl_renderbuffer_image",
+                "EGL_EXT_image_dma_buf_import",
+                "EGL_EXT_image_dma_buf_import_modifiers",
+                "EGL_MESA_image_dma_buf_export",
+            ],
+        );
         registry.write_bindings(StructGenerator, &mut file).unwrap();
     }
 }
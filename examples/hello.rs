@@ -36,7 +36,7 @@ fn main() {
     let context = instance.create_context().unwrap();
     let surface = instance.create_surface(&window).unwrap();
 
-    instance.make_current(Some(&surface), Some(&context));
+    instance.make_current(Some(&surface), Some(&context)).unwrap();
 
     let mut r = 0.0;
 
@@ -58,7 +58,7 @@ fn main() {
 
             Event::RedrawRequested(_) => {
                 unsafe {
-                    let gl = instance.get_glow();
+                    let gl = instance.get_glow().unwrap();
 
                     r += 0.01;
                     if r > 1.0 {
@@ -74,7 +74,9 @@ fn main() {
                 {
                     // instance.make_current(Some(&surface), Some(&context));
 
-                    instance.swap_buffers(&surface);
+                    if let Err(e) = instance.swap_buffers(&surface) {
+                        log::error!("swap_buffers failed: {}", e);
+                    }
                     fps += 1;
                     // println!("time: {:?}",time.elapsed().as_millis() );
                     if time.elapsed().as_millis() > 1000 {
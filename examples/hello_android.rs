@@ -65,7 +65,9 @@ fn main() {
                     // println!("gl error:{}", err);
                 }
                 if let Some(surface) = &surface {
-                    instance.swap_buffers(surface);
+                    if let Err(e) = instance.swap_buffers(surface) {
+                        log::error!("swap_buffers failed: {}", e);
+                    }
                     fps += 1;
                     // println!("time: {:?}",time.elapsed().as_millis() );
                     if time.elapsed().as_millis() > 1000 {
@@ -77,8 +79,8 @@ fn main() {
             }
             Event::Resumed => {
                 let s = instance.create_surface(&window).unwrap();
-                instance.make_current(Some(&s), Some(&context));
-                let context = instance.get_glow();
+                instance.make_current(Some(&s), Some(&context)).unwrap();
+                let context = instance.get_glow().unwrap();
 
                 let context = unsafe {
                     std::mem::transmute::<&'_ glow::Context, &'static glow::Context>(context)
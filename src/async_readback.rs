@@ -0,0 +1,257 @@
+use std::time::{Duration, Instant};
+
+use glow::HasContext;
+
+use crate::readback::{read_format, read_into};
+use crate::{Context, ImageData, Instance, InstanceError, PixelFormat, PixelRect, ReadFormat};
+
+/// 一次异步读取，由 `AsyncReadback::queue` 返回
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ReadbackHandle {
+    id: u64,
+    slot: usize,
+}
+
+/// 已经提交、还没有取走的读取
+#[derive(Debug)]
+struct PendingRead {
+    id: u64,
+    width: u32,
+    height: u32,
+    format: PixelFormat,
+    fence: glow::Fence,
+}
+
+#[derive(Debug)]
+struct ReadbackSlot {
+    buffer: glow::Buffer,
+    /// PBO 已分配的字节数
+    capacity: usize,
+    pending: Option<PendingRead>,
+}
+
+/// 异步像素读取，由 `Instance::create_async_readback` 创建
+///
+/// glReadPixels 写入像素缓冲（PBO）环中的下一个缓冲并插入 GL 栅栏，立即返回；
+/// 一两帧之后栅栏触发，再映射缓冲取出像素，不会让 CPU 等待 GPU 完成渲染。
+///
+/// 所有操作都需要绑定创建时的 Context。环满时新的读取覆盖最早的未取走结果，
+/// 它的句柄之后返回 `InstanceError::ReadbackExpired`。
+/// 释放时不删除 GL 对象（可能没有绑定上下文），需要调用 `destroy`，或随 Context 一起释放。
+#[derive(Debug)]
+pub struct AsyncReadback {
+    context: Context,
+    slots: Vec<ReadbackSlot>,
+    /// 下一次读取使用的缓冲
+    next_slot: usize,
+    next_id: u64,
+}
+
+unsafe impl Send for AsyncReadback {}
+
+impl AsyncReadback {
+    /// 创建 `slots` 个 PBO，`gl` 是 `context` 的函数表，`context` 必须已经绑定
+    pub(crate) fn new(
+        gl: &glow::Context,
+        context: Context,
+        slots: usize,
+    ) -> Result<Self, InstanceError> {
+        let mut readback = Self {
+            context,
+            slots: Vec::with_capacity(slots.max(1)),
+            next_slot: 0,
+            next_id: 0,
+        };
+        for _ in 0..slots.max(1) {
+            match unsafe { gl.create_buffer() } {
+                Ok(buffer) => readback.slots.push(ReadbackSlot {
+                    buffer,
+                    capacity: 0,
+                    pending: None,
+                }),
+                Err(_) => {
+                    let code = unsafe { gl.get_error() };
+                    readback.delete(gl);
+                    return Err(InstanceError::GlCallFailed {
+                        op: "glGenBuffers",
+                        code,
+                    });
+                }
+            }
+        }
+        Ok(readback)
+    }
+
+    /// 创建时的 Context 必须绑定在 `instance` 上
+    fn gl<'a>(&self, instance: &'a Instance) -> Result<&'a glow::Context, InstanceError> {
+        if instance.current().1 != Some(&self.context) {
+            return Err(InstanceError::NoCurrentContext);
+        }
+        instance.get_glow()
+    }
+
+    /// 提交一次读取，读取当前绑定到 GL_READ_FRAMEBUFFER 的帧缓冲
+    ///
+    /// `rect` 为 GL 坐标（原点在左下角）；提交后会 glFlush，栅栏才能触发。
+    pub fn queue(
+        &mut self,
+        instance: &Instance,
+        rect: PixelRect,
+        format: ReadFormat,
+    ) -> Result<ReadbackHandle, InstanceError> {
+        let gl = self.gl(instance)?;
+        let (gl_format, format) = read_format(gl, format);
+        let size = rect.width as usize * rect.height as usize * format.bytes_per_pixel();
+
+        let index = self.next_slot;
+        let slot_count = self.slots.len();
+        let slot = &mut self.slots[index];
+        unsafe {
+            // 环满，丢弃最早的结果
+            if let Some(pending) = slot.pending.take() {
+                gl.delete_sync(pending.fence);
+            }
+            gl.bind_buffer(glow::PIXEL_PACK_BUFFER, Some(slot.buffer));
+            if slot.capacity < size {
+                gl.buffer_data_size(glow::PIXEL_PACK_BUFFER, size as i32, glow::STREAM_READ);
+                slot.capacity = size;
+            }
+        }
+        let result = read_into(gl, rect, gl_format, glow::PixelPackData::BufferOffset(0));
+        unsafe { gl.bind_buffer(glow::PIXEL_PACK_BUFFER, None) };
+        result?;
+
+        let fence = unsafe { gl.fence_sync(glow::SYNC_GPU_COMMANDS_COMPLETE, 0) }.map_err(|_| {
+            InstanceError::GlCallFailed {
+                op: "glFenceSync",
+                code: unsafe { gl.get_error() },
+            }
+        })?;
+        unsafe { gl.flush() };
+
+        let id = self.next_id;
+        self.next_id += 1;
+        self.next_slot = (index + 1) % slot_count;
+        slot.pending = Some(PendingRead {
+            id,
+            width: rect.width,
+            height: rect.height,
+            format,
+            fence,
+        });
+        Ok(ReadbackHandle { id, slot: index })
+    }
+
+    /// 读取完成时取走结果（行从上到下排列），还没有完成时返回 `None`，不等待
+    pub fn poll(
+        &mut self,
+        instance: &Instance,
+        handle: ReadbackHandle,
+    ) -> Result<Option<ImageData>, InstanceError> {
+        let gl = self.gl(instance)?;
+        let fence = self.pending(handle)?.fence;
+        if unsafe { gl.get_sync_status(fence) } != glow::SIGNALED {
+            return Ok(None);
+        }
+        self.take(gl, handle.slot).map(Some)
+    }
+
+    /// 等待读取完成并取走结果，`timeout` 为 `None` 时一直等待；超时返回 `None`
+    pub fn wait(
+        &mut self,
+        instance: &Instance,
+        handle: ReadbackHandle,
+        timeout: Option<Duration>,
+    ) -> Result<Option<ImageData>, InstanceError> {
+        let gl = self.gl(instance)?;
+        let fence = self.pending(handle)?.fence;
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+
+        loop {
+            // glow 的超时参数是 i32 纳秒，长的等待分多次
+            let remaining = match deadline {
+                Some(deadline) => deadline.saturating_duration_since(Instant::now()),
+                None => Duration::MAX,
+            };
+            let timeout = remaining.as_nanos().min(i32::MAX as u128) as i32;
+            match unsafe { gl.client_wait_sync(fence, glow::SYNC_FLUSH_COMMANDS_BIT, timeout) } {
+                glow::ALREADY_SIGNALED | glow::CONDITION_SATISFIED => {
+                    return self.take(gl, handle.slot).map(Some)
+                }
+                glow::TIMEOUT_EXPIRED if remaining.is_zero() => return Ok(None),
+                glow::TIMEOUT_EXPIRED => {}
+                _ => {
+                    return Err(InstanceError::GlCallFailed {
+                        op: "glClientWaitSync",
+                        code: unsafe { gl.get_error() },
+                    })
+                }
+            }
+        }
+    }
+
+    /// 删除 PBO 和未触发的栅栏，需要绑定创建时的 Context
+    pub fn destroy(mut self, instance: &Instance) -> Result<(), InstanceError> {
+        let gl = self.gl(instance)?;
+        self.delete(gl);
+        Ok(())
+    }
+
+    fn delete(&mut self, gl: &glow::Context) {
+        for slot in self.slots.drain(..) {
+            unsafe {
+                if let Some(pending) = slot.pending {
+                    gl.delete_sync(pending.fence);
+                }
+                gl.delete_buffer(slot.buffer);
+            }
+        }
+    }
+
+    /// 句柄对应的读取，已经取走或被覆盖时返回 `ReadbackExpired`
+    fn pending(&self, handle: ReadbackHandle) -> Result<&PendingRead, InstanceError> {
+        self.slots
+            .get(handle.slot)
+            .and_then(|slot| slot.pending.as_ref())
+            .filter(|pending| pending.id == handle.id)
+            .ok_or(InstanceError::ReadbackExpired)
+    }
+
+    /// 映射已完成的缓冲，复制出像素
+    fn take(&mut self, gl: &glow::Context, index: usize) -> Result<ImageData, InstanceError> {
+        let slot = &mut self.slots[index];
+        let pending = match slot.pending.take() {
+            Some(pending) => pending,
+            None => return Err(InstanceError::ReadbackExpired),
+        };
+
+        let mut image = ImageData {
+            width: pending.width,
+            height: pending.height,
+            format: pending.format,
+            data: Vec::new(),
+        };
+        let size = image.stride() * image.height as usize;
+        unsafe {
+            gl.delete_sync(pending.fence);
+            if size > 0 {
+                gl.bind_buffer(glow::PIXEL_PACK_BUFFER, Some(slot.buffer));
+                let ptr =
+                    gl.map_buffer_range(glow::PIXEL_PACK_BUFFER, 0, size as i32, glow::MAP_READ_BIT);
+                if ptr.is_null() {
+                    let code = gl.get_error();
+                    gl.bind_buffer(glow::PIXEL_PACK_BUFFER, None);
+                    return Err(InstanceError::GlCallFailed {
+                        op: "glMapBufferRange",
+                        code,
+                    });
+                }
+                image.data = std::slice::from_raw_parts(ptr, size).to_vec();
+                gl.unmap_buffer(glow::PIXEL_PACK_BUFFER);
+                gl.bind_buffer(glow::PIXEL_PACK_BUFFER, None);
+            }
+        }
+        image.flip_vertical();
+        Ok(image)
+    }
+}
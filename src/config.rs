@@ -0,0 +1,191 @@
+use std::ops::{BitOr, BitOrAssign};
+
+/// Surface 类型，可以用 `|` 组合
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct SurfaceTypes(u32);
+
+impl SurfaceTypes {
+    /// 窗口表面
+    pub const WINDOW: Self = Self(0x0004);
+    /// 离屏 pbuffer 表面
+    pub const PBUFFER: Self = Self(0x0001);
+    /// 离屏 pixmap 表面
+    pub const PIXMAP: Self = Self(0x0002);
+
+    /// 不要求任何表面类型
+    #[inline]
+    pub const fn empty() -> Self {
+        Self(0)
+    }
+
+    /// 由 EGL_SURFACE_TYPE 的位值构造
+    #[inline]
+    pub const fn from_bits(bits: u32) -> Self {
+        Self(bits)
+    }
+
+    /// 位值，和 EGL_SURFACE_TYPE 的位定义一致
+    #[inline]
+    pub const fn bits(&self) -> u32 {
+        self.0
+    }
+
+    /// 是否包含 `other` 的全部类型
+    #[inline]
+    pub const fn contains(&self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl BitOr for SurfaceTypes {
+    type Output = Self;
+
+    #[inline]
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl BitOrAssign for SurfaceTypes {
+    #[inline]
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.0 |= rhs.0;
+    }
+}
+
+/// 配置支持的客户端 API，可以用 `|` 组合
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct RenderableTypes(u32);
+
+impl RenderableTypes {
+    pub const OPENGL_ES: Self = Self(0x0001);
+    pub const OPENVG: Self = Self(0x0002);
+    pub const OPENGL_ES2: Self = Self(0x0004);
+    pub const OPENGL: Self = Self(0x0008);
+    pub const OPENGL_ES3: Self = Self(0x0040);
+
+    /// 由 EGL_RENDERABLE_TYPE 的位值构造
+    #[inline]
+    pub const fn from_bits(bits: u32) -> Self {
+        Self(bits)
+    }
+
+    #[inline]
+    pub const fn bits(&self) -> u32 {
+        self.0
+    }
+
+    /// 是否包含 `other` 的全部 API
+    #[inline]
+    pub const fn contains(&self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl BitOr for RenderableTypes {
+    type Output = Self;
+
+    #[inline]
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+/// 驱动对配置的性能提示（EGL_CONFIG_CAVEAT）
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ConfigCaveat {
+    None,
+    /// 慢速配置，通常是软件实现
+    Slow,
+    /// 不符合规范一致性测试
+    NonConformant,
+}
+
+/// 透明色（EGL_TRANSPARENT_TYPE）
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Transparency {
+    None,
+    /// 等于该颜色的像素视为透明
+    Rgb {
+        red: i32,
+        green: i32,
+        blue: i32,
+    },
+}
+
+/// 一个帧缓冲配置的属性，由 `Instance::configs` 返回
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ConfigInfo {
+    /// EGL_CONFIG_ID，可以填到 `ConfigRequest::config_id` 来指定这个配置
+    pub id: i32,
+    pub red_size: u8,
+    pub green_size: u8,
+    pub blue_size: u8,
+    pub alpha_size: u8,
+    pub depth_size: u8,
+    pub stencil_size: u8,
+    /// 颜色缓冲总位数
+    pub buffer_size: u8,
+    /// MSAA 采样数，0 表示不带 MSAA
+    pub samples: u8,
+    pub renderable_types: RenderableTypes,
+    pub surface_types: SurfaceTypes,
+    pub caveat: ConfigCaveat,
+    /// 对应的本地视觉 ID（X11 visual / Android 像素格式）
+    pub native_visual_id: i32,
+    pub transparency: Transparency,
+    pub min_swap_interval: i32,
+    pub max_swap_interval: i32,
+}
+
+/// 多个配置都满足要求时的排序策略
+///
+/// 全部为 false 时，按驱动（eglChooseConfig）返回的顺序取第一个；
+/// 多个条件同时打开时，按字段声明顺序依次比较。
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct ConfigRanking {
+    /// 优先避开驱动标记为慢速（EGL_SLOW_CONFIG）的配置
+    pub avoid_slow: bool,
+    /// 优先各通道位数与请求完全一致的配置
+    pub prefer_exact: bool,
+    /// 优先不带 MSAA 的配置
+    pub prefer_no_msaa: bool,
+}
+
+/// 帧缓冲配置的选择条件
+///
+/// 各位数都是最小值；Instance 创建时选定一个配置，之后的 Surface 和 Context 共用它。
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ConfigRequest {
+    pub red_size: u8,
+    pub green_size: u8,
+    pub blue_size: u8,
+    pub alpha_size: u8,
+    pub depth_size: u8,
+    pub stencil_size: u8,
+    /// MSAA 采样数，0 表示不要求
+    pub samples: u8,
+    /// 配置必须支持的表面类型
+    pub surface_types: SurfaceTypes,
+    pub ranking: ConfigRanking,
+    /// 直接指定配置（`ConfigInfo::id`），指定时忽略其他条件；WGL / WebGL 下忽略
+    pub config_id: Option<i32>,
+}
+
+impl Default for ConfigRequest {
+    /// RGB888 + 24 位深度，无 alpha、模板、MSAA，支持窗口表面
+    fn default() -> Self {
+        Self {
+            red_size: 8,
+            green_size: 8,
+            blue_size: 8,
+            alpha_size: 0,
+            depth_size: 24,
+            stencil_size: 0,
+            samples: 0,
+            surface_types: SurfaceTypes::WINDOW,
+            ranking: ConfigRanking::default(),
+            config_id: None,
+        }
+    }
+}
@@ -1,20 +1,45 @@
 #[cfg(target_os = "windows")]
-use crate::platform::windows::context::WglContext as ContextInner;
+pub(crate) use crate::platform::windows::context::WglContext as ContextInner;
 
 #[cfg(any(target_os = "linux", target_os = "android"))]
-use crate::platform::android::context::EglContext as ContextInner;
+pub(crate) use crate::platform::android::context::EglContext as ContextInner;
 
 #[cfg(target_arch = "wasm32")]
-use crate::platform::web::context::WebContext as ContextInner;
+pub(crate) use crate::platform::web::context::WebContext as ContextInner;
 
-#[derive(Debug, Eq, PartialEq)]
+use pi_share::Share;
+
+use crate::DebugFilter;
+
+// 可以 clone，clone 出的是同一个上下文
+// 每个 Context 有自己的 GL 函数表，第一次 make_current 时加载
+#[derive(Debug, Clone, Eq, PartialEq)]
 pub struct Context {
-    pub context: ContextInner,
+    pub context: Share<ContextInner>,
 }
 
 unsafe impl Sync for Context {}
 unsafe impl Send for Context {}
 
+/// 创建 Context 的选项，见 `Instance::create_context_with_options`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct ContextOptions {
+    /// 请求调试上下文（EGL_CONTEXT_OPENGL_DEBUG / WGL_CONTEXT_DEBUG_BIT_ARB），
+    /// 并把 GL 调试消息按过滤条件转发到 `log`；为 `None` 时创建普通上下文。
+    /// WebGL 不支持调试输出，忽略这个选项
+    pub debug: Option<DebugFilter>,
+}
+
+impl ContextOptions {
+    /// 使用默认过滤条件的调试上下文
+    #[inline]
+    pub fn debug() -> Self {
+        Self {
+            debug: Some(DebugFilter::default()),
+        }
+    }
+}
+
 impl Drop for Context {
     fn drop(&mut self) {
         {}
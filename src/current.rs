@@ -0,0 +1,57 @@
+use pi_share::Share;
+
+use crate::{context::ContextInner, Context, Instance, InstanceError, Surface};
+
+/// `Instance::bind` 返回的绑定守卫
+///
+/// 存在期间 Instance 被可变借用，不能再 `make_current`；释放时恢复 `bind` 之前的绑定。
+#[derive(Debug)]
+pub struct CurrentGuard<'a> {
+    pub(crate) instance: &'a mut Instance,
+    pub(crate) surface: Surface,
+    /// GL 函数表的所有者，一般是绑定的 Context
+    pub(crate) gl_owner: Share<ContextInner>,
+    pub(crate) previous_surface: Option<Surface>,
+    pub(crate) previous_context: Option<Context>,
+}
+
+impl<'a> CurrentGuard<'a> {
+    /// 绑定的 Context 的函数表
+    #[inline]
+    pub fn gl(&self) -> &glow::Context {
+        self.gl_owner.glow()
+    }
+
+    /// 绑定的 Surface
+    #[inline]
+    pub fn surface(&self) -> &Surface {
+        &self.surface
+    }
+
+    /// 交换绑定的 Surface 的双缓冲
+    #[inline]
+    pub fn swap_buffers(&self) -> Result<(), InstanceError> {
+        self.instance.swap_buffers(&self.surface)
+    }
+
+    /// 嵌套绑定；返回的守卫释放时恢复到这个守卫的绑定
+    #[inline]
+    pub fn bind(
+        &mut self,
+        surface: &Surface,
+        context: &Context,
+    ) -> Result<CurrentGuard<'_>, InstanceError> {
+        self.instance.bind(surface, context)
+    }
+}
+
+impl Drop for CurrentGuard<'_> {
+    fn drop(&mut self) {
+        let surface = self.previous_surface.take();
+        let context = self.previous_context.take();
+        // Drop 中无法返回错误，只记录日志
+        if let Err(err) = self.instance.make_current(surface.as_ref(), context.as_ref()) {
+            log::error!("恢复之前的绑定失败: {}", err);
+        }
+    }
+}
@@ -0,0 +1,322 @@
+use glow::HasContext;
+
+/// 调试消息的来源（GL_DEBUG_SOURCE_*）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DebugSource {
+    Api,
+    WindowSystem,
+    ShaderCompiler,
+    ThirdParty,
+    /// 应用自己插入的消息，包括 `push_debug_group`
+    Application,
+    Other,
+}
+
+impl DebugSource {
+    /// 由 GL 枚举值转换，未知的值视为 `Other`
+    pub fn from_gl(value: u32) -> Self {
+        match value {
+            glow::DEBUG_SOURCE_API => DebugSource::Api,
+            glow::DEBUG_SOURCE_WINDOW_SYSTEM => DebugSource::WindowSystem,
+            glow::DEBUG_SOURCE_SHADER_COMPILER => DebugSource::ShaderCompiler,
+            glow::DEBUG_SOURCE_THIRD_PARTY => DebugSource::ThirdParty,
+            glow::DEBUG_SOURCE_APPLICATION => DebugSource::Application,
+            _ => DebugSource::Other,
+        }
+    }
+}
+
+/// 调试消息的类型（GL_DEBUG_TYPE_*）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DebugType {
+    Error,
+    DeprecatedBehavior,
+    UndefinedBehavior,
+    Portability,
+    Performance,
+    Marker,
+    PushGroup,
+    PopGroup,
+    Other,
+}
+
+impl DebugType {
+    /// 由 GL 枚举值转换，未知的值视为 `Other`
+    pub fn from_gl(value: u32) -> Self {
+        match value {
+            glow::DEBUG_TYPE_ERROR => DebugType::Error,
+            glow::DEBUG_TYPE_DEPRECATED_BEHAVIOR => DebugType::DeprecatedBehavior,
+            glow::DEBUG_TYPE_UNDEFINED_BEHAVIOR => DebugType::UndefinedBehavior,
+            glow::DEBUG_TYPE_PORTABILITY => DebugType::Portability,
+            glow::DEBUG_TYPE_PERFORMANCE => DebugType::Performance,
+            glow::DEBUG_TYPE_MARKER => DebugType::Marker,
+            glow::DEBUG_TYPE_PUSH_GROUP => DebugType::PushGroup,
+            glow::DEBUG_TYPE_POP_GROUP => DebugType::PopGroup,
+            _ => DebugType::Other,
+        }
+    }
+}
+
+/// 调试消息的严重程度（GL_DEBUG_SEVERITY_*），从低到高排列
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum DebugSeverity {
+    Notification,
+    Low,
+    Medium,
+    High,
+}
+
+impl DebugSeverity {
+    /// 由 GL 枚举值转换，未知的值视为 `Notification`
+    pub fn from_gl(value: u32) -> Self {
+        match value {
+            glow::DEBUG_SEVERITY_HIGH => DebugSeverity::High,
+            glow::DEBUG_SEVERITY_MEDIUM => DebugSeverity::Medium,
+            glow::DEBUG_SEVERITY_LOW => DebugSeverity::Low,
+            _ => DebugSeverity::Notification,
+        }
+    }
+
+    /// 转发到 `log` 时使用的级别
+    pub fn log_level(self) -> log::Level {
+        match self {
+            DebugSeverity::High => log::Level::Error,
+            DebugSeverity::Medium => log::Level::Warn,
+            DebugSeverity::Low => log::Level::Info,
+            DebugSeverity::Notification => log::Level::Debug,
+        }
+    }
+}
+
+/// 调试消息的过滤条件，见 `ContextOptions::debug`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct DebugFilter {
+    /// 只转发这些来源的消息，为空时不限制
+    pub sources: Vec<DebugSource>,
+    /// 只转发这些类型的消息，为空时不限制
+    pub types: Vec<DebugType>,
+    /// 不转发的消息 ID，用于屏蔽驱动反复报告的已知消息
+    pub ignored_ids: Vec<u32>,
+    /// 低于这个严重程度的消息不转发
+    pub min_severity: DebugSeverity,
+}
+
+impl Default for DebugFilter {
+    /// 转发所有来源和类型，跳过 Notification（驱动会报告大量缓冲区分配之类的信息）
+    fn default() -> Self {
+        Self {
+            sources: Vec::new(),
+            types: Vec::new(),
+            ignored_ids: Vec::new(),
+            min_severity: DebugSeverity::Low,
+        }
+    }
+}
+
+impl DebugFilter {
+    /// 这条消息是否需要转发
+    pub fn accepts(
+        &self,
+        source: DebugSource,
+        ty: DebugType,
+        id: u32,
+        severity: DebugSeverity,
+    ) -> bool {
+        severity >= self.min_severity
+            && (self.sources.is_empty() || self.sources.contains(&source))
+            && (self.types.is_empty() || self.types.contains(&ty))
+            && !self.ignored_ids.contains(&id)
+    }
+}
+
+/// 打开 GL 调试输出，把通过 `filter` 的消息转发到 `log`（target 为 "gl"）
+///
+/// 使用 GL_DEBUG_OUTPUT_SYNCHRONOUS，消息在出错的 GL 调用内回调，方便定位调用栈。
+/// 需要 GL 4.3 / GLES 3.2 或 GL_KHR_debug，不支持时只打印一条警告。
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn install_debug_output(gl: &mut glow::Context, filter: DebugFilter) {
+    if !gl.supports_debug() {
+        log::warn!("GL_KHR_debug is unavailable, GL debug output disabled");
+        return;
+    }
+
+    unsafe {
+        gl.enable(glow::DEBUG_OUTPUT);
+        gl.enable(glow::DEBUG_OUTPUT_SYNCHRONOUS);
+        gl.debug_message_callback(move |source, ty, id, severity, message| {
+            let source = DebugSource::from_gl(source);
+            let ty = DebugType::from_gl(ty);
+            let severity = DebugSeverity::from_gl(severity);
+            if filter.accepts(source, ty, id, severity) {
+                log::log!(
+                    target: "gl",
+                    severity.log_level(),
+                    "[{:?} {:?} 0x{:X}] {}",
+                    source,
+                    ty,
+                    id,
+                    message
+                );
+            }
+        });
+    }
+}
+
+/// 开始一个调试分组（glPushDebugGroup），在 RenderDoc 等工具中显示为一段命令
+///
+/// 不支持 KHR_debug 时（包括 WebGL）什么也不做。
+pub fn push_debug_group(gl: &glow::Context, message: &str) {
+    if gl.supports_debug() {
+        unsafe { gl.push_debug_group(glow::DEBUG_SOURCE_APPLICATION, 0, message) };
+    }
+}
+
+/// 结束 `push_debug_group` 开始的调试分组（glPopDebugGroup）
+pub fn pop_debug_group(gl: &glow::Context) {
+    if gl.supports_debug() {
+        unsafe { gl.pop_debug_group() };
+    }
+}
+
+/// 开始一个调试分组，返回的 `DebugGroup` 释放时结束
+pub fn debug_group<'a>(gl: &'a glow::Context, message: &str) -> DebugGroup<'a> {
+    push_debug_group(gl, message);
+    DebugGroup { gl }
+}
+
+/// 由 `debug_group` 返回，释放时调用 glPopDebugGroup
+#[must_use = "调试分组在 DebugGroup 释放时结束"]
+#[derive(Debug)]
+pub struct DebugGroup<'a> {
+    gl: &'a glow::Context,
+}
+
+impl Drop for DebugGroup<'_> {
+    fn drop(&mut self) {
+        pop_debug_group(self.gl);
+    }
+}
+
+/// 给 GL 对象命名（glObjectLabel），调试消息和调试工具中会显示这个名字
+///
+/// `identifier` 为对象类型，如 `glow::TEXTURE`、`glow::BUFFER`、`glow::PROGRAM`；
+/// `name` 为对象的 GL 名字，如 `texture.0.get()`。不支持 KHR_debug 时什么也不做。
+pub fn object_label(gl: &glow::Context, identifier: u32, name: u32, label: &str) {
+    if gl.supports_debug() {
+        unsafe { gl.object_label(identifier, name, Some(label)) };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_gl() {
+        let sources = [
+            (glow::DEBUG_SOURCE_API, DebugSource::Api),
+            (glow::DEBUG_SOURCE_WINDOW_SYSTEM, DebugSource::WindowSystem),
+            (
+                glow::DEBUG_SOURCE_SHADER_COMPILER,
+                DebugSource::ShaderCompiler,
+            ),
+            (glow::DEBUG_SOURCE_THIRD_PARTY, DebugSource::ThirdParty),
+            (glow::DEBUG_SOURCE_APPLICATION, DebugSource::Application),
+            (glow::DEBUG_SOURCE_OTHER, DebugSource::Other),
+            (0xDEAD, DebugSource::Other),
+        ];
+        for (value, source) in sources {
+            assert_eq!(DebugSource::from_gl(value), source, "0x{:X}", value);
+        }
+
+        let types = [
+            (glow::DEBUG_TYPE_ERROR, DebugType::Error),
+            (
+                glow::DEBUG_TYPE_DEPRECATED_BEHAVIOR,
+                DebugType::DeprecatedBehavior,
+            ),
+            (
+                glow::DEBUG_TYPE_UNDEFINED_BEHAVIOR,
+                DebugType::UndefinedBehavior,
+            ),
+            (glow::DEBUG_TYPE_PORTABILITY, DebugType::Portability),
+            (glow::DEBUG_TYPE_PERFORMANCE, DebugType::Performance),
+            (glow::DEBUG_TYPE_MARKER, DebugType::Marker),
+            (glow::DEBUG_TYPE_PUSH_GROUP, DebugType::PushGroup),
+            (glow::DEBUG_TYPE_POP_GROUP, DebugType::PopGroup),
+            (glow::DEBUG_TYPE_OTHER, DebugType::Other),
+            (0xDEAD, DebugType::Other),
+        ];
+        for (value, ty) in types {
+            assert_eq!(DebugType::from_gl(value), ty, "0x{:X}", value);
+        }
+
+        let severities = [
+            (
+                glow::DEBUG_SEVERITY_HIGH,
+                DebugSeverity::High,
+                log::Level::Error,
+            ),
+            (
+                glow::DEBUG_SEVERITY_MEDIUM,
+                DebugSeverity::Medium,
+                log::Level::Warn,
+            ),
+            (
+                glow::DEBUG_SEVERITY_LOW,
+                DebugSeverity::Low,
+                log::Level::Info,
+            ),
+            (
+                glow::DEBUG_SEVERITY_NOTIFICATION,
+                DebugSeverity::Notification,
+                log::Level::Debug,
+            ),
+            (0xDEAD, DebugSeverity::Notification, log::Level::Debug),
+        ];
+        for (value, severity, level) in severities {
+            assert_eq!(DebugSeverity::from_gl(value), severity, "0x{:X}", value);
+            assert_eq!(severity.log_level(), level);
+        }
+    }
+
+    #[test]
+    fn accepts() {
+        use DebugSeverity::*;
+        use DebugSource::*;
+        use DebugType::Error as TypeError;
+        use DebugType::Performance;
+
+        let default = DebugFilter::default();
+        let restricted = DebugFilter {
+            sources: vec![Api, ShaderCompiler],
+            types: vec![TypeError],
+            ignored_ids: vec![131185],
+            min_severity: Medium,
+        };
+
+        // (过滤条件, 来源, 类型, ID, 严重程度, 是否转发)
+        let cases = [
+            (&default, Api, TypeError, 1, High, true),
+            (&default, Application, Performance, 1, Low, true),
+            (&default, Api, TypeError, 1, Notification, false),
+            (&restricted, Api, TypeError, 1, High, true),
+            (&restricted, ShaderCompiler, TypeError, 1, Medium, true),
+            (&restricted, Api, TypeError, 1, Low, false),
+            (&restricted, ThirdParty, TypeError, 1, High, false),
+            (&restricted, Api, Performance, 1, High, false),
+            (&restricted, Api, TypeError, 131185, High, false),
+        ];
+        for (filter, source, ty, id, severity, expected) in cases {
+            assert_eq!(
+                filter.accepts(source, ty, id, severity),
+                expected,
+                "{:?} {:?} {} {:?}",
+                source,
+                ty,
+                id,
+                severity
+            );
+        }
+    }
+}
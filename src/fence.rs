@@ -0,0 +1,49 @@
+use std::os::fd::OwnedFd;
+use std::time::Duration;
+
+use crate::platform::android::sync::EglFence as FenceInner;
+use crate::InstanceError;
+
+/// GPU 栅栏，由 `Instance::insert_fence` 插入；可以移到其他线程等待
+///
+/// 典型用法：加载线程上传纹理后插入栅栏，渲染线程 `server_wait` 之后再使用纹理。
+/// 必须在创建它的 Instance 之前释放。
+#[derive(Debug)]
+pub struct Fence {
+    pub(crate) fence: FenceInner,
+}
+
+unsafe impl Sync for Fence {}
+unsafe impl Send for Fence {}
+
+impl Fence {
+    /// CPU 等待，`timeout` 为 None 时一直等待；返回 false 表示超时
+    ///
+    /// 等待前会刷新插入栅栏的上下文的命令，避免该上下文在当前线程时永远等不到。
+    #[inline]
+    pub fn client_wait(&self, timeout: Option<Duration>) -> Result<bool, InstanceError> {
+        self.fence.client_wait(timeout)
+    }
+
+    /// 当前线程绑定的 Context 在 GPU 上等待栅栏，之后提交的命令在栅栏触发后才执行；不阻塞 CPU
+    ///
+    /// 插入栅栏的上下文需要先 glFlush，否则可能永远不会触发。
+    #[inline]
+    pub fn server_wait(&self) -> Result<(), InstanceError> {
+        self.fence.server_wait()
+    }
+
+    /// 导出为 Linux sync_file，每次调用返回一个新的 fd，可以传给其他进程
+    ///
+    /// 只有 `Instance::insert_native_fence` 或 `import_native_fence` 得到的栅栏可以导出。
+    #[inline]
+    pub fn export_fd(&self) -> Result<OwnedFd, InstanceError> {
+        self.fence.export_fd()
+    }
+
+    /// 是否已触发，不等待
+    #[inline]
+    pub fn is_signaled(&self) -> Result<bool, InstanceError> {
+        self.fence.is_signaled()
+    }
+}
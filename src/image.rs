@@ -0,0 +1,88 @@
+use std::os::fd::{AsFd, BorrowedFd, OwnedFd};
+
+use pi_share::Share;
+
+use crate::platform::android::image::EglImage as ImageInner;
+
+/// EGLImage：GPU 上的一块图像存储，可以绑定为不同共享组的 Context 中的纹理，零拷贝共享
+///
+/// 可以 clone，clone 出的是同一个图像；可以在线程间传递。必须在创建它的 Instance 之前释放。
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Image {
+    pub(crate) image: Share<ImageInner>,
+}
+
+unsafe impl Sync for Image {}
+unsafe impl Send for Image {}
+
+/// DMA-BUF 的一个平面
+#[derive(Debug, Clone, Copy)]
+pub struct DmabufPlane<'a> {
+    /// 导入时 EGL 不接管 fd，导入后可以关闭
+    pub fd: BorrowedFd<'a>,
+    /// 平面数据在 fd 中的字节偏移
+    pub offset: u32,
+    /// 行距，字节
+    pub stride: u32,
+    /// DRM 格式修饰符（tiling / 压缩），None 表示由驱动推断；各平面必须相同
+    pub modifier: Option<u64>,
+}
+
+/// `Instance::import_dmabuf` 的参数
+#[derive(Debug, Clone)]
+pub struct DmabufDesc<'a> {
+    pub width: u32,
+    pub height: u32,
+    /// DRM fourcc 格式，如 DRM_FORMAT_ARGB8888（'AR24'）、DRM_FORMAT_NV12（'NV12'）
+    pub fourcc: u32,
+    /// 1 到 4 个平面，如 NV12 有 Y 和 UV 两个平面
+    pub planes: Vec<DmabufPlane<'a>>,
+}
+
+/// 一个可以导入的格式修饰符，由 `Instance::dmabuf_modifiers` 返回
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DmabufModifier {
+    pub modifier: u64,
+    /// 只能绑定为 GL_TEXTURE_EXTERNAL_OES 纹理（`Instance::bind_image_to_external_texture`）
+    pub external_only: bool,
+}
+
+/// DMA-BUF 导出的一个平面
+#[derive(Debug)]
+pub struct ExportedDmabufPlane {
+    /// 每个平面一个 fd；多个平面在同一个 DMA-BUF 中时，是同一个 DMA-BUF 的不同 fd
+    pub fd: OwnedFd,
+    pub offset: u32,
+    pub stride: u32,
+}
+
+/// `Instance::export_dmabuf` 的结果，fd 可以传给其他进程导入
+#[derive(Debug)]
+pub struct ExportedDmabuf {
+    /// DRM fourcc 格式
+    pub fourcc: u32,
+    /// DRM 格式修饰符，各平面相同
+    pub modifier: u64,
+    pub planes: Vec<ExportedDmabufPlane>,
+}
+
+impl ExportedDmabuf {
+    /// 对应的导入参数，图像大小由调用者提供（导出时 EGL 不返回大小）
+    pub fn as_desc(&self, width: u32, height: u32) -> DmabufDesc<'_> {
+        DmabufDesc {
+            width,
+            height,
+            fourcc: self.fourcc,
+            planes: self
+                .planes
+                .iter()
+                .map(|plane| DmabufPlane {
+                    fd: plane.fd.as_fd(),
+                    offset: plane.offset,
+                    stride: plane.stride,
+                    modifier: Some(self.modifier),
+                })
+                .collect(),
+        }
+    }
+}
@@ -1,10 +1,30 @@
-use std::sync::RwLock;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+use std::os::fd::OwnedFd;
+use std::sync::{Mutex, RwLock};
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
+#[cfg(feature = "fps")]
+use std::time::Duration;
 
 use glow::HasContext;
 use pi_share::Share;
 use raw_window_handle::{HasDisplayHandle, HasWindowHandle};
 
-use super::{Context, PowerPreference, Surface};
+#[cfg(any(target_os = "linux", target_os = "android"))]
+use super::{ConfigInfo, DmabufDesc, DmabufModifier, ExportedDmabuf, Fence, Image, PbufferFormat};
+#[cfg(target_os = "linux")]
+use super::{AdapterInfo, DisplayPlatform, SurfaceTypes};
+use super::{
+    ConfigRequest, Context, ContextOptions, CurrentGuard, ImageData, PixelRect, PowerPreference,
+    PresentMode, ReadFormat, Surface,
+};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::async_readback::AsyncReadback;
+use crate::context::ContextInner;
+use crate::pacer::{self, FramePacer, FramePacing};
+use crate::readback;
+#[cfg(feature = "fps")]
+use crate::stats::{FrameStats, FrameStatsRecorder};
 
 // use crate::GL;
 #[cfg(target_os = "windows")]
@@ -19,6 +39,8 @@ use crate::platform::web::instance::WebInstance as InstanceInner;
 
 #[cfg(any(target_os = "linux", target_os = "android"))]
 use crate::platform::android::instance::EglInstance as InstanceInner;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+use crate::platform::android::image::TEXTURE_EXTERNAL_OES;
 
 
 crate::init_env!();
@@ -30,26 +52,134 @@ lazy_static!{
 
 #[derive(Debug)]
 pub struct Instance {
+    // 当前绑定的 Surface 和 Context；放在 instance 之前，先于显示连接释放
+    surface: Option<Surface>,
+    context: Option<Context>,
     instance: InstanceInner,
+    pacer: Mutex<FramePacer>,
+    #[cfg(feature = "fps")]
+    frame_stats: Mutex<FrameStatsRecorder>,
 }
 
 unsafe impl Sync for Instance {}
 unsafe impl Send for Instance {}
 
 impl Instance {
+    fn from_inner(instance: InstanceInner) -> Self {
+        Self {
+            surface: None,
+            context: None,
+            instance,
+            pacer: Mutex::new(FramePacer::new()),
+            #[cfg(feature = "fps")]
+            frame_stats: Mutex::new(FrameStatsRecorder::new()),
+        }
+    }
+
     // Display 封装在里面，不对外曝露
     // is_vsync: SwapBuffers 是否 重置同步
+    // power: 有多个显卡时按它选择；Linux 下需要 EGL_EXT_platform_base 和 EGL_EXT_explicit_device，
+    // 不支持时退回 eglGetDisplay，由驱动决定
     #[inline]
     pub fn new(power: PowerPreference, is_vsync: bool) -> Result<Self, InstanceError> {
+        Self::with_config(power, is_vsync, &ConfigRequest::default())
+    }
+
+    // 按 config 选择帧缓冲配置；之后创建的 Surface 和 Context 共用这个配置
+    // wasm32 下忽略 config
+    #[inline]
+    pub fn with_config(
+        power: PowerPreference,
+        is_vsync: bool,
+        config: &ConfigRequest,
+    ) -> Result<Self, InstanceError> {
         // Windows下: LowPower 集显, HighPerformance 独显
         {
-            Ok(Self {
-                instance: InstanceInner::new(power, is_vsync)?,
-            })
+            let instance = InstanceInner::new(power, is_vsync, config)?;
+            Ok(Self::from_inner(instance))
         }
     }
 
+    // 显示连接上的所有 EGLConfig
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    #[inline]
+    pub fn configs(&self) -> Result<Vec<ConfigInfo>, InstanceError> {
+        self.instance.configs()
+    }
+
+    // 创建时选定的 EGLConfig
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    #[inline]
+    pub fn config(&self) -> ConfigInfo {
+        self.instance.config()
+    }
+
+    // 在窗口所属的显示连接（Xlib Display / XCB 连接 / Wayland wl_display）上创建，
+    // 之后 create_surface 只接受同一个显示连接上的窗口
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    #[inline]
+    pub fn from_display_handle<D: HasDisplayHandle>(
+        display: &D,
+        power: PowerPreference,
+        is_vsync: bool,
+        config: &ConfigRequest,
+    ) -> Result<Self, InstanceError> {
+        let handle = display
+            .display_handle()
+            .map_err(|_| InstanceError::IncompatibleWindowHandle)?
+            .as_raw();
+        let instance = InstanceInner::from_display_handle(handle, power, is_vsync, config)?;
+        Ok(Self::from_inner(instance))
+    }
+
+    // 用 eglGetPlatformDisplay 创建，依次尝试 platforms，用第一个可用的平台
+    #[cfg(target_os = "linux")]
+    #[inline]
+    pub fn with_platforms(
+        platforms: &[DisplayPlatform],
+        power: PowerPreference,
+        is_vsync: bool,
+        config: &ConfigRequest,
+    ) -> Result<Self, InstanceError> {
+        let instance = InstanceInner::with_platforms(platforms, power, is_vsync, config)?;
+        Ok(Self::from_inner(instance))
+    }
+
+    // 列出所有 EGL 设备（显卡）
+    #[cfg(target_os = "linux")]
+    #[inline]
+    pub fn adapters() -> Result<Vec<AdapterInfo>, InstanceError> {
+        InstanceInner::adapters()
+    }
+
+    // 在指定设备上创建，id 为 AdapterInfo::id；使用设备平台，只能创建 pbuffer Surface
+    #[cfg(target_os = "linux")]
+    #[inline]
+    pub fn with_adapter(
+        id: usize,
+        is_vsync: bool,
+        config: &ConfigRequest,
+    ) -> Result<Self, InstanceError> {
+        let instance = InstanceInner::with_adapter(id, is_vsync, config)?;
+        Ok(Self::from_inner(instance))
+    }
+
+    // 无窗口系统时使用（如 CI 上的 Mesa llvmpipe），
+    // 按 DisplayPlatform::FALLBACK_ORDER 选择平台，只能创建 pbuffer Surface
+    #[cfg(target_os = "linux")]
+    #[inline]
+    pub fn headless(power: PowerPreference, is_vsync: bool) -> Result<Self, InstanceError> {
+        let config = ConfigRequest {
+            surface_types: SurfaceTypes::PBUFFER,
+            ..Default::default()
+        };
+        Self::with_platforms(&DisplayPlatform::FALLBACK_ORDER, power, is_vsync, &config)
+    }
+
     // 带双缓冲的 Surface
+    // 所有平台的窗口都用 raw-window-handle 0.6 的借用句柄；
+    // Android 下 Surface 持有 ANativeWindow 的引用，其他平台窗口必须比 Surface 活得久
+    // Wayland 窗口没有大小，返回 InstanceError::SizeRequired，改用 create_surface_with_size
     #[inline]
     pub fn create_surface<W: HasWindowHandle + HasDisplayHandle>(
         &self,
@@ -57,29 +187,89 @@ impl Instance {
     ) -> Result<Surface, InstanceError> {
         {
             let surface = self.instance.create_surface(window)?;
+            self.select_pacing_backend(&mut self.pacer.lock().unwrap());
             Ok(Surface {
                 surface: Share::new(surface),
             })
         }
     }
 
+    // 同 create_surface，Wayland 窗口的 Surface 初始大小为 width x height；其他窗口系统忽略大小
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    #[inline]
+    pub fn create_surface_with_size<W: HasWindowHandle + HasDisplayHandle>(
+        &self,
+        window: &W,
+        width: u32,
+        height: u32,
+    ) -> Result<Surface, InstanceError> {
+        let surface = self.instance.create_surface_with_size(window, width, height)?;
+        self.select_pacing_backend(&mut self.pacer.lock().unwrap());
+        Ok(Surface {
+            surface: Share::new(surface),
+        })
+    }
+
+    // 离屏 pbuffer Surface，用法和窗口 Surface 相同，swap_buffers 无效果
+    // 需要 ConfigRequest::surface_types 包含 SurfaceTypes::PBUFFER（Instance::new 的默认配置不包含），
+    // 否则返回 InstanceError::PbufferUnsupported
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    #[inline]
+    pub fn create_pbuffer_surface(
+        &self,
+        width: u32,
+        height: u32,
+        format: PbufferFormat,
+    ) -> Result<Surface, InstanceError> {
+        let surface = self.instance.create_pbuffer_surface(width, height, format)?;
+        Ok(Surface {
+            surface: Share::new(surface),
+        })
+    }
+
     // GLES 3.0 / WebGL2
     #[inline]
     pub fn create_context(&self) -> Result<Context, InstanceError> {
-        {
-            let context = self.instance.create_context()?;
-            Ok(Context { context })
-        }
+        self.create_context_with_options(None, &ContextOptions::default())
+    }
+
+    // 按 options 创建 Context，share 不为 None 时和 create_shared_context 一样加入它的共享组
+    // options.debug 为 Some 时请求调试上下文，第一次 make_current 时打开 GL 调试输出，
+    // 消息按过滤条件转发到 log（target 为 "gl"）；驱动不支持时退回普通上下文并打印警告
+    #[inline]
+    pub fn create_context_with_options(
+        &self,
+        share: Option<&Context>,
+        options: &ContextOptions,
+    ) -> Result<Context, InstanceError> {
+        let context = self
+            .instance
+            .create_context(share.map(|share| &*share.context), options)?;
+        Ok(Context {
+            context: Share::new(context),
+        })
+    }
+
+    // 和 share 在同一个共享组的 Context：纹理、缓冲、着色器、程序等对象互相可见，
+    // FBO、VAO 等容器对象不共享；可以在加载线程上绑定它上传资源，再在渲染线程上使用
+    // 对象在另一个 Context 中使用前，上传线程需要 glFinish 或用 Fence 同步
+    // wasm32 下 WebGL 没有共享组，返回 RequiredExtensionUnavailable
+    #[inline]
+    pub fn create_shared_context(&self, share: &Context) -> Result<Context, InstanceError> {
+        self.create_context_with_options(Some(share), &ContextOptions::default())
     }
 
     // 调用了这个之后，gl的函数 才能用；
     // wasm32 cfg 空实现
+    // Instance 会保留绑定的 Surface 和 Context 的 clone（供 current 和 bind 使用）：
+    // 调用者释放它们之后，EGL 对象不会立即销毁，要等到绑定改变或 make_current(None, None)
+    // 要销毁一个窗口的 Surface（如窗口关闭时），先解除绑定再释放
     #[inline]
     pub fn make_current<'a>(
         &'a mut self,
         surface: Option<&'a Surface>,
         context: Option<&Context>,
-    ) {
+    ) -> Result<(), InstanceError> {
         let mut s = None;
         if let Some(t) = surface {
             s = Some(&t.surface)
@@ -87,44 +277,506 @@ impl Instance {
 
         let mut c = None;
         if let Some(t) = context {
-            c = Some(&t.context)
+            c = Some(t.context.as_ref())
         }
 
         let s = s.map(|v| v.as_ref());
-        self.instance.make_current(s, c);
+        self.instance.make_current(s, c)?;
+
+        // 函数表在上下文绑定之后加载
+        if let Some(owner) = gl_owner(surface, context) {
+            owner.glow();
+        }
+        self.surface = surface.cloned();
+        self.context = context.cloned();
+        Ok(())
+    }
+
+    // 当前线程上由这个 Instance 绑定的 Surface 和 Context
+    // EGL 下按 eglGetCurrent* 核对：当前线程的绑定被其他 Instance 或其他库（直接调用 eglMakeCurrent）
+    // 改变后，返回 (None, None)；Windows 下不核对，其他库改变 WGL 绑定后仍返回之前的绑定
+    #[inline]
+    pub fn current(&self) -> (Option<&Surface>, Option<&Context>) {
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        {
+            let surface = self.surface.as_ref().map(|surface| surface.surface.as_ref());
+            let context = self.context.as_ref().map(|context| context.context.as_ref());
+            if !self.instance.is_current(surface, context) {
+                return (None, None);
+            }
+        }
+        (self.surface.as_ref(), self.context.as_ref())
+    }
+
+    // 当前绑定的 Context 的函数表；没有绑定 Context 时返回 NoCurrentContext
+    #[inline]
+    pub fn get_glow(&self) -> Result<&glow::Context, InstanceError> {
+        match gl_owner(self.surface.as_ref(), self.context.as_ref()) {
+            Some(owner) => Ok(owner.glow()),
+            None => Err(InstanceError::NoCurrentContext),
+        }
+    }
+
+    // 绑定 surface 和 context，返回的 CurrentGuard 释放时恢复之前的绑定
+    // guard 存在期间 Instance 被可变借用，GL 只能通过 CurrentGuard::gl 调用
+    pub fn bind<'a>(
+        &'a mut self,
+        surface: &Surface,
+        context: &Context,
+    ) -> Result<CurrentGuard<'a>, InstanceError> {
+        let previous_surface = self.surface.clone();
+        let previous_context = self.context.clone();
+        self.make_current(Some(surface), Some(context))?;
+
+        // make_current 成功后一定有函数表的所有者
+        let gl_owner = match gl_owner(Some(surface), Some(context)) {
+            Some(owner) => owner.clone(),
+            None => return Err(InstanceError::NoCurrentContext),
+        };
+        Ok(CurrentGuard {
+            instance: self,
+            surface: surface.clone(),
+            gl_owner,
+            previous_surface,
+            previous_context,
+        })
+    }
+
+    // 在当前绑定的 Context 的命令流中插入栅栏，之前提交的命令执行完后触发
+    // 需要先 make_current 绑定 Context；EGL 1.4 下需要 EGL_KHR_fence_sync
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    #[inline]
+    pub fn insert_fence(&self) -> Result<Fence, InstanceError> {
+        let fence = self.instance.insert_fence()?;
+        Ok(Fence { fence })
+    }
+
+    // 插入可以导出为 sync_file 的栅栏（EGL_ANDROID_native_fence_sync，Mesa 在 Linux 上也支持），
+    // 用 Fence::export_fd 交给其他进程；插入后会 glFlush，导出的 fd 才有效
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn insert_native_fence(&self) -> Result<Fence, InstanceError> {
+        let fence = self.instance.native_fence(None)?;
+        unsafe { self.get_glow()?.flush() };
+        Ok(Fence { fence })
+    }
+
+    // 导入其他进程的 sync_file，通常接着调用 Fence::server_wait 让当前 Context 等待它
+    // fd 的所有权交给 Fence，失败时 fd 被关闭；需要先 make_current 绑定 Context
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    #[inline]
+    pub fn import_native_fence(&self, fd: OwnedFd) -> Result<Fence, InstanceError> {
+        let fence = self.instance.native_fence(Some(fd))?;
+        Ok(Fence { fence })
+    }
+
+    // 由 context 中的 2D 纹理创建 EGLImage，texture 的 level 层必须已经定义
+    // 图像和纹理共用存储：之后对纹理的修改在图像上可见，纹理删除后图像仍然有效
+    // 修改纹理后，其他 Context 使用图像前需要用 Fence 同步
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    #[inline]
+    pub fn create_image_from_texture(
+        &self,
+        context: &Context,
+        texture: glow::Texture,
+        level: u32,
+    ) -> Result<Image, InstanceError> {
+        let image =
+            self.instance
+                .create_image_from_texture(&context.context, texture.0.get(), level as i32)?;
+        Ok(Image {
+            image: Share::new(image),
+        })
     }
 
+    // 由 context 中的渲染缓冲创建 EGLImage，渲染缓冲的存储必须已经分配（glRenderbufferStorage）
+    #[cfg(any(target_os = "linux", target_os = "android"))]
     #[inline]
-    pub fn get_glow<'a>(&'a self) -> &glow::Context {
-        self.instance.get_glow()
+    pub fn create_image_from_renderbuffer(
+        &self,
+        context: &Context,
+        renderbuffer: glow::Renderbuffer,
+    ) -> Result<Image, InstanceError> {
+        let image = self
+            .instance
+            .create_image_from_renderbuffer(&context.context, renderbuffer.0.get())?;
+        Ok(Image {
+            image: Share::new(image),
+        })
+    }
+
+    // 把 image 作为当前 Context 中绑定在 GL_TEXTURE_2D 上的纹理的存储（glEGLImageTargetTexture2DOES）
+    // 纹理原来的内容被丢弃；需要 GL_OES_EGL_image
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn bind_image_to_texture(&self, image: &Image) -> Result<(), InstanceError> {
+        let gl = self.get_glow()?;
+        if !gl.supported_extensions().contains("GL_OES_EGL_image") {
+            return Err(InstanceError::RequiredExtensionUnavailable);
+        }
+        image.image.bind_to_texture(glow::TEXTURE_2D)
+    }
+
+    // 同 bind_image_to_texture，绑定到 GL_TEXTURE_EXTERNAL_OES 上的纹理，着色器中用 samplerExternalOES 采样
+    // YUV 格式的 DMA-BUF 通常只能这样使用，由驱动转换为 RGB；需要 GL_OES_EGL_image_external
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn bind_image_to_external_texture(&self, image: &Image) -> Result<(), InstanceError> {
+        let gl = self.get_glow()?;
+        if !gl.supported_extensions().contains("GL_OES_EGL_image_external") {
+            return Err(InstanceError::RequiredExtensionUnavailable);
+        }
+        image.image.bind_to_texture(TEXTURE_EXTERNAL_OES)
+    }
+
+    // 导入 DMA-BUF（摄像头、视频解码器等的输出）为 Image，零拷贝；不需要绑定 Context
+    // 需要 EGL_EXT_image_dma_buf_import，指定修饰符时还需要 EGL_EXT_image_dma_buf_import_modifiers
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    #[inline]
+    pub fn import_dmabuf(&self, desc: &DmabufDesc) -> Result<Image, InstanceError> {
+        let image = self.instance.import_dmabuf(desc)?;
+        Ok(Image {
+            image: Share::new(image),
+        })
+    }
+
+    // 把 image 导出为 DMA-BUF，零拷贝交给其他进程（如编码器）；每次调用导出新的 fd
+    // 需要 EGL_MESA_image_dma_buf_export；导出前渲染的命令需要用 Fence 同步
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    #[inline]
+    pub fn export_dmabuf(&self, image: &Image) -> Result<ExportedDmabuf, InstanceError> {
+        image.image.export_dmabuf()
+    }
+
+    // 可以导入的 DMA-BUF 格式（DRM fourcc）；需要 EGL_EXT_image_dma_buf_import_modifiers
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    #[inline]
+    pub fn dmabuf_formats(&self) -> Result<Vec<u32>, InstanceError> {
+        self.instance.dmabuf_formats()
+    }
+
+    // 格式 fourcc 可以导入的修饰符；需要 EGL_EXT_image_dma_buf_import_modifiers
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    #[inline]
+    pub fn dmabuf_modifiers(&self, fourcc: u32) -> Result<Vec<DmabufModifier>, InstanceError> {
+        self.instance.dmabuf_modifiers(fourcc)
+    }
+
+    // 读取 surface 的后缓冲（swap_buffers 之前调用），用于截图和图像对比测试
+    // 实际读取的是当前绑定到 GL_READ_FRAMEBUFFER 的帧缓冲，绑定了 FBO 时读取 FBO；不能绑定 GL_PIXEL_PACK_BUFFER
+    // rect 为 GL 坐标（原点在左下角），结果已翻转为从上到下的行顺序
+    // surface 必须是这个 Instance 当前绑定的 Surface，否则返回 SurfaceNotCurrent
+    pub fn read_pixels(
+        &self,
+        surface: &Surface,
+        rect: PixelRect,
+        format: ReadFormat,
+    ) -> Result<ImageData, InstanceError> {
+        if self.current().0 != Some(surface) {
+            return Err(InstanceError::SurfaceNotCurrent);
+        }
+        readback::read_pixels(self.get_glow()?, rect, format)
+    }
+
+    // 异步读取，每帧读取（录屏、推流）时不会让 CPU 等待 GPU；slots 为 PBO 环的大小，2 到 3 通常足够
+    // 使用当前绑定的 Context，之后的读取也都需要绑定它；wasm32 下 WebGL 不支持映射缓冲，没有这个接口
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn create_async_readback(&self, slots: usize) -> Result<AsyncReadback, InstanceError> {
+        let context = match self.current().1 {
+            Some(context) => context.clone(),
+            None => return Err(InstanceError::NoCurrentContext),
+        };
+        AsyncReadback::new(self.get_glow()?, context, slots)
+    }
+
+    // 设置 Surface 的呈现模式，返回实际生效的模式
+    // EGL 下按配置的 EGL_MIN/MAX_SWAP_INTERVAL 限制，不支持 AdaptiveFifo 时退回 Fifo；
+    // Surface 正绑定在当前线程时立即生效，否则在下一次绑定时生效
+    // wasm32 下由浏览器决定，总是返回 Fifo
+    #[inline]
+    pub fn set_present_mode(
+        &self,
+        surface: &Surface,
+        mode: PresentMode,
+    ) -> Result<PresentMode, InstanceError> {
+        self.instance.set_present_mode(&surface.surface, mode)
+    }
+
+    // 帧率限制，在 swap_buffers 中生效；可以随时修改
+    // Android 启用 swappy 特性时由 Swappy 实现，其他情况下软件等待（sleep + 忙等）
+    // wasm32 下由浏览器决定，忽略
+    #[inline]
+    pub fn set_frame_pacing(&self, pacing: FramePacing) {
+        let mut pacer = self.pacer.lock().unwrap();
+        pacer.set_pacing(pacing);
+        self.select_pacing_backend(&mut pacer);
+    }
+
+    // 选择帧率限制由后端（Swappy）还是软件等待实现，两者只有一个起作用
+    // Swappy 在设置了窗口之后才启用，所以创建窗口 Surface 之后也要重新选择
+    fn select_pacing_backend(&self, pacer: &mut FramePacer) {
+        let native = self.instance.set_frame_pacing(&pacer.pacing());
+        pacer.set_native(native);
+    }
+
+    #[inline]
+    pub fn frame_pacing(&self) -> FramePacing {
+        self.pacer.lock().unwrap().pacing()
     }
 
     // 交换 Surface 中的 双缓冲
-    // wasm32 cfg 空实现
+    // wasm32 cfg 空实现；std::time::Instant 在 wasm32 下不可用，不做帧率限制和帧统计
+    #[inline]
+    pub fn swap_buffers(&self, surface: &Surface) -> Result<(), InstanceError> {
+        #[cfg(target_arch = "wasm32")]
+        {
+            self.instance.swap_buffers(&surface.surface)
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            // 等待和交换时不持有锁，其他线程可以随时调用 set_frame_pacing
+            let target = self.pacer.lock().unwrap().swap_target();
+            if let Some((target, spin)) = target {
+                pacer::wait_until(target, spin);
+            }
+            let swap_start = Instant::now();
+            self.instance.swap_buffers(&surface.surface)?;
+            self.pacer.lock().unwrap().presented(swap_start);
+
+            #[cfg(feature = "fps")]
+            self.frame_stats.lock().unwrap().record(Instant::now());
+            Ok(())
+        }
+    }
+
+    // 最近 FRAME_WINDOW 帧的帧时间统计，帧时间是相邻两次 swap_buffers 的间隔
+    // wasm32 下不记录，总是为空
+    #[cfg(feature = "fps")]
+    #[inline]
+    pub fn frame_stats(&self) -> FrameStats {
+        self.frame_stats.lock().unwrap().stats()
+    }
+
+    // 清空帧统计，如切换场景后
+    #[cfg(feature = "fps")]
     #[inline]
-    pub fn swap_buffers(&self, surface: &Surface) {
-        // let gl = self.instance.get_glow();
-        // let e = unsafe { gl.get_error() };
-        // if e != 0 {
-        //     log::error!("opengl error: {}!!", e);
-        // }
-        self.instance.swap_buffers(&surface.surface)
+    pub fn reset_frame_stats(&self) {
+        self.frame_stats.lock().unwrap().reset();
+    }
+
+    // 直方图和卡顿判断使用的帧预算，默认 1/60 秒
+    #[cfg(feature = "fps")]
+    #[inline]
+    pub fn set_frame_budget(&self, budget: Duration) {
+        self.frame_stats.lock().unwrap().set_budget(budget);
     }
 }
 
-#[derive(Debug)]
+// 绑定时 GL 函数表的所有者
+// wasm32 下 Surface 本身就是 WebGL 上下文，同时绑定了 Surface 时用它的函数表
+fn gl_owner<'a>(
+    surface: Option<&'a Surface>,
+    context: Option<&'a Context>,
+) -> Option<&'a Share<ContextInner>> {
+    #[cfg(target_arch = "wasm32")]
+    if let (Some(surface), Some(_)) = (surface, context) {
+        return Some(&surface.surface);
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    let _ = surface;
+    context.map(|context| &context.context)
+}
+
+/// 实例相关操作的错误
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum InstanceError {
-    // TODO
+    /// 缺少必要的扩展
     RequiredExtensionUnavailable,
+    /// 窗口句柄与当前平台不兼容
     IncompatibleWindowHandle,
+    /// 窗口不属于实例的显示连接，如在无窗口系统（surfaceless / 设备平台）的实例上创建窗口 Surface
+    DisplayMismatch,
+    /// 上下文创建失败
     ContextCreationFailed,
+    /// JNI 调用失败
     JNIFailed,
+    /// EGL 调用失败
+    ///
+    /// - `op`: 失败的 EGL 函数名，如 `eglMakeCurrent`
+    /// - `code`: `eglGetError` 返回的原始错误码
+    /// - `name`: 错误码对应的名字，如 `EGL_BAD_MATCH`
+    EglCallFailed {
+        op: &'static str,
+        code: i32,
+        name: &'static str,
+    },
+    /// Win32 / WGL 调用失败，`code` 为 `GetLastError` 的返回值
+    Win32CallFailed { op: &'static str, code: u32 },
+    /// 动态库加载失败
+    LibraryLoadFailed(&'static str),
+    /// 没有绑定 Context（未调用 make_current，或绑定的是 None）
+    NoCurrentContext,
+    /// 参数中的 Surface 不是当前绑定的 Surface
+    SurfaceNotCurrent,
+    /// GL 调用失败，`code` 为 `glGetError` 的返回值
+    GlCallFailed { op: &'static str, code: u32 },
+    /// 异步读取的结果已经取走，或被之后的读取覆盖
+    ReadbackExpired,
+    /// Wayland 窗口的表面大小由应用决定，需要用 `Instance::create_surface_with_size` 创建
+    SizeRequired,
+    /// 实例选定的配置不支持 pbuffer 表面，需要用 `Instance::with_config` 请求 `SurfaceTypes::PBUFFER`
+    PbufferUnsupported,
 }
 
-// impl Display for InstanceError {
-//     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-//         match self {
+impl InstanceError {
+    /// 由 EGL 错误码构造错误
+    pub fn egl(op: &'static str, code: i32) -> Self {
+        Self::EglCallFailed {
+            op,
+            code,
+            name: egl_error_name(code),
+        }
+    }
 
-//         }
-//     }
-// }
+    /// 如果是 EGL 调用失败，返回原始错误码
+    pub fn egl_code(&self) -> Option<i32> {
+        match self {
+            Self::EglCallFailed { code, .. } => Some(*code),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for InstanceError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::RequiredExtensionUnavailable => write!(f, "required extension unavailable"),
+            Self::IncompatibleWindowHandle => write!(f, "incompatible window handle"),
+            Self::DisplayMismatch => {
+                write!(f, "window does not belong to the instance's display connection")
+            }
+            Self::ContextCreationFailed => write!(f, "context creation failed"),
+            Self::JNIFailed => write!(f, "jni call failed"),
+            Self::EglCallFailed { op, code, name } => {
+                write!(f, "{} failed: {} (0x{:04X})", op, name, code)
+            }
+            Self::Win32CallFailed { op, code } => write!(f, "{} failed: error code {}", op, code),
+            Self::LibraryLoadFailed(name) => write!(f, "failed to load {}", name),
+            Self::NoCurrentContext => write!(f, "no context is current"),
+            Self::SurfaceNotCurrent => write!(f, "surface is not current"),
+            Self::GlCallFailed { op, code } => write!(f, "{} failed: GL error 0x{:04X}", op, code),
+            Self::ReadbackExpired => write!(f, "readback result expired"),
+            Self::SizeRequired => write!(
+                f,
+                "Wayland windows need an initial size, use Instance::create_surface_with_size"
+            ),
+            Self::PbufferUnsupported => write!(
+                f,
+                "config does not support pbuffer surfaces, request SurfaceTypes::PBUFFER with Instance::with_config"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for InstanceError {}
+
+/// EGL 错误码 转 名字
+pub fn egl_error_name(code: i32) -> &'static str {
+    match code {
+        0x3000 => "EGL_SUCCESS",
+        0x3001 => "EGL_NOT_INITIALIZED",
+        0x3002 => "EGL_BAD_ACCESS",
+        0x3003 => "EGL_BAD_ALLOC",
+        0x3004 => "EGL_BAD_ATTRIBUTE",
+        0x3005 => "EGL_BAD_CONFIG",
+        0x3006 => "EGL_BAD_CONTEXT",
+        0x3007 => "EGL_BAD_CURRENT_SURFACE",
+        0x3008 => "EGL_BAD_DISPLAY",
+        0x3009 => "EGL_BAD_MATCH",
+        0x300A => "EGL_BAD_NATIVE_PIXMAP",
+        0x300B => "EGL_BAD_NATIVE_WINDOW",
+        0x300C => "EGL_BAD_PARAMETER",
+        0x300D => "EGL_BAD_SURFACE",
+        0x300E => "EGL_CONTEXT_LOST",
+        0x321B => "EGL_BAD_STREAM_KHR",
+        0x321C => "EGL_BAD_STATE_KHR",
+        0x322B => "EGL_BAD_DEVICE_EXT",
+        0x3352 => "EGL_BAD_OUTPUT_LAYER_EXT",
+        0x3353 => "EGL_BAD_OUTPUT_PORT_EXT",
+        _ => "EGL_UNKNOWN_ERROR",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn egl_error_names() {
+        assert_eq!(egl_error_name(0x3000), "EGL_SUCCESS");
+        assert_eq!(egl_error_name(0x3009), "EGL_BAD_MATCH");
+        assert_eq!(egl_error_name(0x300E), "EGL_CONTEXT_LOST");
+        assert_eq!(egl_error_name(0x322B), "EGL_BAD_DEVICE_EXT");
+        assert_eq!(egl_error_name(0x1234), "EGL_UNKNOWN_ERROR");
+        assert_eq!(egl_error_name(-1), "EGL_UNKNOWN_ERROR");
+    }
+
+    #[test]
+    fn egl_error_display() {
+        let err = InstanceError::egl("eglMakeCurrent", 0x3009);
+        assert_eq!(
+            err,
+            InstanceError::EglCallFailed {
+                op: "eglMakeCurrent",
+                code: 0x3009,
+                name: "EGL_BAD_MATCH",
+            }
+        );
+        assert_eq!(err.to_string(), "eglMakeCurrent failed: EGL_BAD_MATCH (0x3009)");
+        assert_eq!(
+            InstanceError::egl("eglCreateContext", 0x42).to_string(),
+            "eglCreateContext failed: EGL_UNKNOWN_ERROR (0x0042)"
+        );
+    }
+
+    #[test]
+    fn error_display() {
+        let cases = [
+            (
+                InstanceError::RequiredExtensionUnavailable,
+                "required extension unavailable",
+            ),
+            (
+                InstanceError::Win32CallFailed {
+                    op: "wglMakeCurrent",
+                    code: 2000,
+                },
+                "wglMakeCurrent failed: error code 2000",
+            ),
+            (
+                InstanceError::LibraryLoadFailed("libEGL.so.1"),
+                "failed to load libEGL.so.1",
+            ),
+            (
+                InstanceError::DisplayMismatch,
+                "window does not belong to the instance's display connection",
+            ),
+            (InstanceError::NoCurrentContext, "no context is current"),
+            (
+                InstanceError::GlCallFailed {
+                    op: "glReadPixels",
+                    code: 0x502,
+                },
+                "glReadPixels failed: GL error 0x0502",
+            ),
+            (
+                InstanceError::SizeRequired,
+                "Wayland windows need an initial size, use Instance::create_surface_with_size",
+            ),
+            (
+                InstanceError::PbufferUnsupported,
+                "config does not support pbuffer surfaces, request SurfaceTypes::PBUFFER with Instance::with_config",
+            ),
+        ];
+        for (err, message) in cases {
+            assert_eq!(err.to_string(), message);
+        }
+    }
+}
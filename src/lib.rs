@@ -1,16 +1,42 @@
 #[macro_use]
 extern crate lazy_static;
 
+mod config;
 mod gl;
 mod instance;
 mod surface;
 mod context;
+mod debug;
+mod current;
+mod pacer;
+mod readback;
+#[cfg(not(target_arch = "wasm32"))]
+mod async_readback;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+mod fence;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+mod image;
+#[cfg(feature = "fps")]
+mod stats;
 pub mod macros;
 
 pub mod platform;
+pub use config::*;
 pub use instance::*;
 pub use surface::*;
 pub use context::*;
+pub use debug::*;
+pub use current::*;
+pub use pacer::*;
+pub use readback::*;
+#[cfg(not(target_arch = "wasm32"))]
+pub use async_readback::*;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub use fence::*;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub use image::*;
+#[cfg(feature = "fps")]
+pub use stats::*;
 
 /// Power Preference when choosing a physical adapter.
 #[repr(C)]
@@ -20,4 +46,47 @@ pub enum PowerPreference {
     LowPower = 0,
     /// Windows下: 独显
     HighPerformance = 1,
-}
\ No newline at end of file
+}
+
+/// 一个 EGL 设备（显卡），由 `Instance::adapters` 返回。
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct AdapterInfo {
+    /// 用于 `Instance::with_adapter`
+    pub id: usize,
+    /// DRM 设备节点，如 `/dev/dri/renderD128`，优先渲染节点
+    pub drm_node: Option<String>,
+    /// 驱动不支持 EGL_EXT_device_query_name 时为 None
+    pub vendor: Option<String>,
+    /// 驱动不支持 EGL_EXT_device_query_name 时为 None
+    pub renderer: Option<String>,
+    /// 是否是软件实现（如 llvmpipe）
+    pub is_software: bool,
+}
+
+/// EGL 显示连接所用的平台，见 `Instance::with_platforms`。
+///
+/// X11 / Wayland / GBM 连接的是默认的 X 服务器 / Wayland 合成器 / DRM 设备。
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum DisplayPlatform {
+    /// EGL_MESA_platform_surfaceless: 无窗口系统，只能使用 pbuffer
+    Surfaceless,
+    /// EGL_EXT_platform_device: 直接使用 EGL 设备
+    Device,
+    /// EGL_KHR_platform_gbm
+    Gbm,
+    /// EGL_KHR_platform_x11
+    X11,
+    /// EGL_KHR_platform_wayland
+    Wayland,
+}
+
+impl DisplayPlatform {
+    /// 自动选择时的尝试顺序：先尝试不依赖窗口系统的平台
+    pub const FALLBACK_ORDER: [DisplayPlatform; 5] = [
+        DisplayPlatform::Surfaceless,
+        DisplayPlatform::Device,
+        DisplayPlatform::Gbm,
+        DisplayPlatform::Wayland,
+        DisplayPlatform::X11,
+    ];
+}
@@ -0,0 +1,271 @@
+use std::time::{Duration, Instant};
+
+/// 帧率限制的配置，由 `Instance::set_frame_pacing` 设置
+///
+/// Android 启用 `swappy` 特性时由 Swappy 实现，其他情况下在 `swap_buffers` 中软件等待。
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FramePacing {
+    /// 目标帧率，`None` 表示不限制
+    pub target_fps: Option<f64>,
+    /// 软件等待时，最后这段时间用忙等代替 sleep，弥补系统 sleep 的精度；默认 1 毫秒
+    pub spin: Duration,
+}
+
+impl Default for FramePacing {
+    fn default() -> Self {
+        Self {
+            target_fps: None,
+            spin: Duration::from_millis(1),
+        }
+    }
+}
+
+impl FramePacing {
+    /// 限制到 `fps` 帧每秒
+    pub fn fps(fps: f64) -> Self {
+        Self {
+            target_fps: Some(fps),
+            ..Default::default()
+        }
+    }
+
+    /// 每帧的目标时间
+    pub fn frame_time(&self) -> Option<Duration> {
+        self.target_fps
+            .filter(|fps| fps.is_finite() && *fps > 0.0)
+            .map(|fps| Duration::from_secs_f64(1.0 / fps))
+    }
+}
+
+/// 软件帧率限制
+///
+/// 以 swap_buffers 返回的时刻为帧边界：交换前等到 `下一帧的边界 - 交换本身的耗时`，
+/// 交换耗时取最近几帧的滑动平均，使帧边界的间隔均匀。
+#[derive(Debug)]
+pub(crate) struct FramePacer {
+    pacing: FramePacing,
+    frame_time: Option<Duration>,
+    /// 由后端（如 Swappy）实现时，软件等待不起作用
+    native: bool,
+    /// 下一帧 swap_buffers 应该返回的时刻
+    deadline: Option<Instant>,
+    /// swap_buffers 本身的耗时，滑动平均
+    swap_cost: Duration,
+}
+
+impl FramePacer {
+    pub(crate) fn new() -> Self {
+        Self {
+            pacing: FramePacing::default(),
+            frame_time: None,
+            native: false,
+            deadline: None,
+            swap_cost: Duration::ZERO,
+        }
+    }
+
+    pub(crate) fn pacing(&self) -> FramePacing {
+        self.pacing
+    }
+
+    /// 修改帧率限制，不改变由谁实现（见 `set_native`）
+    pub(crate) fn set_pacing(&mut self, pacing: FramePacing) {
+        self.pacing = pacing;
+        self.frame_time = pacing.frame_time();
+        self.deadline = None;
+    }
+
+    /// `native` 为 true 表示后端自己实现了帧率限制，软件等待不再起作用
+    pub(crate) fn set_native(&mut self, native: bool) {
+        if self.native != native {
+            self.native = native;
+            self.deadline = None;
+        }
+    }
+
+    /// 交换之前调用，返回应该开始交换的时刻和忙等时间，不需要等待时返回 `None`
+    ///
+    /// 只计算，不等待；调用者释放锁之后再用 `wait_until` 等待。
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn swap_target(&self) -> Option<(Instant, Duration)> {
+        let deadline = match (self.native, self.deadline) {
+            (false, Some(deadline)) => deadline,
+            _ => return None,
+        };
+        let target = deadline.checked_sub(self.swap_cost).unwrap_or(deadline);
+        Some((target, self.pacing.spin))
+    }
+
+    /// 交换之后调用，`swap_start` 是调用交换之前的时刻
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn presented(&mut self, swap_start: Instant) {
+        self.presented_at(swap_start, Instant::now());
+    }
+
+    /// `presented`，`now` 为交换返回的时刻
+    #[cfg(not(target_arch = "wasm32"))]
+    fn presented_at(&mut self, swap_start: Instant, now: Instant) {
+        let frame_time = match (self.native, self.frame_time) {
+            (false, Some(frame_time)) => frame_time,
+            _ => return,
+        };
+
+        let cost = now.saturating_duration_since(swap_start);
+        // 交换会阻塞在垂直同步上，超过一帧的耗时不计入
+        self.swap_cost = (self.swap_cost * 7 + cost.min(frame_time)) / 8;
+
+        // 落后超过一帧时不追赶，从现在重新开始计时
+        self.deadline = match self.deadline {
+            Some(deadline) if deadline + frame_time > now => Some(deadline + frame_time),
+            _ => Some(now + frame_time),
+        };
+    }
+}
+
+/// 等到 `target`：先 sleep，最后 `spin` 时间忙等
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn wait_until(target: Instant, spin: Duration) {
+    let now = Instant::now();
+    if target <= now {
+        return;
+    }
+    let sleep = sleep_time(target - now, spin);
+    if !sleep.is_zero() {
+        std::thread::sleep(sleep);
+    }
+    while Instant::now() < target {
+        std::hint::spin_loop();
+    }
+}
+
+/// 还要等 `remaining` 时 sleep 的时间，剩下不超过 `spin` 的部分忙等
+#[cfg(not(target_arch = "wasm32"))]
+fn sleep_time(remaining: Duration, spin: Duration) -> Duration {
+    remaining.saturating_sub(spin)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MS: Duration = Duration::from_millis(1);
+
+    fn pacer(pacing: FramePacing, native: bool) -> FramePacer {
+        let mut pacer = FramePacer::new();
+        pacer.set_pacing(pacing);
+        pacer.set_native(native);
+        pacer
+    }
+
+    #[test]
+    fn frame_time() {
+        assert_eq!(FramePacing::default().frame_time(), None);
+        assert_eq!(FramePacing::fps(50.0).frame_time(), Some(20 * MS));
+        for fps in [0.0, -30.0, f64::NAN, f64::INFINITY] {
+            assert_eq!(FramePacing::fps(fps).frame_time(), None);
+        }
+    }
+
+    #[test]
+    fn unlimited_never_waits() {
+        let mut pacer = pacer(FramePacing::default(), false);
+        let start = Instant::now();
+        pacer.presented_at(start, start + 5 * MS);
+        assert_eq!(pacer.swap_target(), None);
+    }
+
+    #[test]
+    fn native_never_waits() {
+        let mut pacer = pacer(FramePacing::fps(100.0), true);
+        let start = Instant::now();
+        pacer.presented_at(start, start + 5 * MS);
+        assert_eq!(pacer.swap_target(), None);
+    }
+
+    #[test]
+    fn deadline() {
+        let mut pacer = pacer(FramePacing::fps(100.0), false);
+        // 第一帧之前不等待
+        assert_eq!(pacer.swap_target(), None);
+
+        // 交换耗时 2 毫秒，滑动平均为 2 / 8 毫秒；下一帧在 10 毫秒后返回
+        let start = Instant::now();
+        let presented = start + 2 * MS;
+        pacer.presented_at(start, presented);
+        let deadline = presented + 10 * MS;
+        let swap_cost = Duration::from_micros(250);
+        assert_eq!(pacer.swap_target(), Some((deadline - swap_cost, MS)));
+
+        // 按时交换，下一帧的边界在上一个边界的 10 毫秒后，不受交换返回时刻的抖动影响
+        pacer.presented_at(deadline - 2 * MS, deadline + MS);
+        let swap_cost = (swap_cost * 7 + 3 * MS) / 8;
+        let deadline = deadline + 10 * MS;
+        assert_eq!(pacer.swap_target(), Some((deadline - swap_cost, MS)));
+
+        // 落后超过一帧，从交换返回的时刻重新计时
+        let late = deadline + 15 * MS;
+        pacer.presented_at(late - MS, late);
+        let swap_cost = (swap_cost * 7 + MS) / 8;
+        assert_eq!(pacer.swap_target(), Some((late + 10 * MS - swap_cost, MS)));
+    }
+
+    #[test]
+    fn swap_cost_clamped_to_frame_time() {
+        // 阻塞在垂直同步上的 50 毫秒只按一帧（10 毫秒）计入
+        let mut pacer = pacer(FramePacing::fps(100.0), false);
+        let start = Instant::now();
+        pacer.presented_at(start, start + 50 * MS);
+        let (target, _) = pacer.swap_target().unwrap();
+        assert_eq!(target, start + 60 * MS - Duration::from_micros(1250));
+    }
+
+    #[test]
+    fn set_pacing_resets_deadline() {
+        let mut pacer = pacer(FramePacing::fps(100.0), false);
+        let start = Instant::now();
+        pacer.presented_at(start, start + MS);
+        assert!(pacer.swap_target().is_some());
+
+        pacer.set_pacing(FramePacing::fps(30.0));
+        assert_eq!(pacer.swap_target(), None);
+        assert_eq!(pacer.pacing(), FramePacing::fps(30.0));
+    }
+
+    #[test]
+    fn pacing_set_before_surface() {
+        // 创建 Surface 之前设置帧率，Swappy 还没有窗口，先由软件等待
+        let mut pacer = pacer(FramePacing::fps(100.0), false);
+        let start = Instant::now();
+        pacer.presented_at(start, start + MS);
+        assert!(pacer.swap_target().is_some());
+
+        // 创建 Surface 之后 Swappy 启用，软件等待不再起作用，帧率设置保留
+        pacer.set_native(true);
+        assert_eq!(pacer.swap_target(), None);
+        pacer.presented_at(start + 10 * MS, start + 11 * MS);
+        assert_eq!(pacer.swap_target(), None);
+        assert_eq!(pacer.pacing(), FramePacing::fps(100.0));
+
+        // 再退回软件等待时不沿用之前的帧边界
+        pacer.set_native(false);
+        assert_eq!(pacer.swap_target(), None);
+        pacer.presented_at(start + 20 * MS, start + 21 * MS);
+        assert!(pacer.swap_target().is_some());
+    }
+
+    #[test]
+    fn sleep_time_leaves_spin() {
+        assert_eq!(sleep_time(5 * MS, MS), 4 * MS);
+        assert_eq!(sleep_time(MS, MS), Duration::ZERO);
+        assert_eq!(sleep_time(Duration::from_micros(500), MS), Duration::ZERO);
+        assert_eq!(sleep_time(5 * MS, Duration::ZERO), 5 * MS);
+    }
+
+    #[test]
+    fn wait_until_target() {
+        let start = Instant::now();
+        wait_until(start - MS, MS);
+        wait_until(start + 3 * MS, MS);
+        assert!(Instant::now() >= start + 3 * MS);
+    }
+}
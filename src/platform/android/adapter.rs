@@ -0,0 +1,173 @@
+use std::ffi::CStr;
+
+use super::{
+    display::query_devices,
+    egl::{self, types::EGLDeviceEXT, EGLint},
+    util::EGL_FUNCTIONS,
+};
+use crate::{AdapterInfo, InstanceError, PowerPreference};
+
+/// EGL_EXT_device_drm_render_node
+const DRM_RENDER_NODE_FILE_EXT: EGLint = 0x3377;
+/// EGL_EXT_device_query_name
+const RENDERER_EXT: EGLint = 0x335F;
+
+/// 读取设备的字符串属性，不支持时返回 None
+unsafe fn device_string(device: EGLDeviceEXT, name: EGLint) -> Option<String> {
+    let egl = &EGL_FUNCTIONS.0;
+    let value = egl.QueryDeviceStringEXT(device, name);
+    if value.is_null() {
+        // 清掉 EGL_BAD_PARAMETER
+        egl.GetError();
+        None
+    } else {
+        Some(CStr::from_ptr(value).to_string_lossy().into_owned())
+    }
+}
+
+/// 读取设备信息
+unsafe fn adapter_info(id: usize, device: EGLDeviceEXT) -> AdapterInfo {
+    let extensions = device_string(device, egl::EXTENSIONS as EGLint).unwrap_or_default();
+    let has_extension = |name: &str| extensions.split_whitespace().any(|e| e == name);
+
+    // 优先渲染节点，不需要 DRM master 权限
+    let mut drm_node = None;
+    if has_extension("EGL_EXT_device_drm_render_node") {
+        drm_node = device_string(device, DRM_RENDER_NODE_FILE_EXT);
+    }
+    if drm_node.is_none() && has_extension("EGL_EXT_device_drm") {
+        drm_node = device_string(device, egl::DRM_DEVICE_FILE_EXT as EGLint);
+    }
+
+    let (mut vendor, mut renderer) = (None, None);
+    if has_extension("EGL_EXT_device_query_name") {
+        vendor = device_string(device, egl::VENDOR as EGLint);
+        renderer = device_string(device, RENDERER_EXT);
+    }
+
+    AdapterInfo {
+        id,
+        is_software: has_extension("EGL_MESA_device_software") || drm_node.is_none(),
+        drm_node,
+        vendor,
+        renderer,
+    }
+}
+
+/// 所有 EGL 设备及其信息，下标即 `AdapterInfo::id`
+pub(crate) unsafe fn query_adapters() -> Result<Vec<(EGLDeviceEXT, AdapterInfo)>, InstanceError> {
+    let egl = &EGL_FUNCTIONS.0;
+    if !egl.QueryDeviceStringEXT.is_loaded() {
+        return Err(InstanceError::RequiredExtensionUnavailable);
+    }
+
+    Ok(query_devices()?
+        .into_iter()
+        .enumerate()
+        .map(|(id, device)| (device, adapter_info(id, device)))
+        .collect())
+}
+
+/// 是否是启动时使用的显卡（sysfs 的 boot_vga），混合显卡的笔记本上一般是集显
+fn is_boot_vga(adapter: &AdapterInfo) -> bool {
+    let node = match adapter.drm_node.as_ref().and_then(|node| node.rsplit('/').next()) {
+        Some(node) => node,
+        None => return false,
+    };
+    std::fs::read_to_string(format!("/sys/class/drm/{}/device/boot_vga", node))
+        .map(|v| v.trim() == "1")
+        .unwrap_or(false)
+}
+
+/// 按电源偏好选择设备，返回 `adapters` 的下标
+///
+/// 硬件设备优先于软件设备；HighPerformance 优先非启动显卡（独显），LowPower 优先启动显卡。
+pub(crate) fn select_adapter(adapters: &[AdapterInfo], power: &PowerPreference) -> Option<usize> {
+    select_adapter_with(adapters, power, is_boot_vga)
+}
+
+/// 窗口系统平台上需要用 EGL_EXT_explicit_device 指定的设备，返回 `adapters` 的下标
+///
+/// 硬件设备不超过一个时返回 None，由驱动选择默认设备。
+pub(crate) fn explicit_adapter(adapters: &[AdapterInfo], power: &PowerPreference) -> Option<usize> {
+    explicit_adapter_with(adapters, power, is_boot_vga)
+}
+
+fn select_adapter_with(
+    adapters: &[AdapterInfo],
+    power: &PowerPreference,
+    is_boot_vga: impl Fn(&AdapterInfo) -> bool,
+) -> Option<usize> {
+    (0..adapters.len()).min_by_key(|i| {
+        let adapter = &adapters[*i];
+        let boot_vga = is_boot_vga(adapter);
+        let preferred = match power {
+            PowerPreference::HighPerformance => !boot_vga,
+            PowerPreference::LowPower => boot_vga,
+        };
+        (adapter.is_software, !preferred)
+    })
+}
+
+fn explicit_adapter_with(
+    adapters: &[AdapterInfo],
+    power: &PowerPreference,
+    is_boot_vga: impl Fn(&AdapterInfo) -> bool,
+) -> Option<usize> {
+    let hardware = adapters.iter().filter(|adapter| !adapter.is_software);
+    if hardware.count() < 2 {
+        return None;
+    }
+    select_adapter_with(adapters, power, is_boot_vga)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn adapter(id: usize, is_software: bool) -> AdapterInfo {
+        AdapterInfo {
+            id,
+            drm_node: None,
+            vendor: None,
+            renderer: None,
+            is_software,
+        }
+    }
+
+    #[test]
+    fn explicit_adapter_follows_power() {
+        use PowerPreference::*;
+
+        // 设备 1 是启动显卡（集显）
+        let boot_vga = |adapter: &AdapterInfo| adapter.id == 1;
+        let hybrid = [adapter(0, true), adapter(1, false), adapter(2, false)];
+        let single = [adapter(0, true), adapter(1, false)];
+        let software = [adapter(0, true), adapter(1, true)];
+
+        // (设备, 电源偏好, 指定的设备)
+        let cases: [(&[AdapterInfo], _, _); 6] = [
+            (&hybrid, HighPerformance, Some(2)),
+            (&hybrid, LowPower, Some(1)),
+            (&single, HighPerformance, None),
+            (&single, LowPower, None),
+            (&software, HighPerformance, None),
+            (&[], HighPerformance, None),
+        ];
+        for (adapters, power, expected) in cases {
+            assert_eq!(
+                explicit_adapter_with(adapters, &power, boot_vga),
+                expected,
+                "{:?} {:?}",
+                adapters.iter().map(|a| a.is_software).collect::<Vec<_>>(),
+                power
+            );
+        }
+
+        // 不需要指定设备时，设备平台仍然按同样的规则选择：硬件设备优先，其次按电源偏好
+        let select = |adapters: &[AdapterInfo], power| select_adapter_with(adapters, &power, boot_vga);
+        assert_eq!(select(&single, LowPower), Some(1));
+        assert_eq!(select(&software, HighPerformance), Some(0));
+        assert_eq!(select(&software, LowPower), Some(1));
+    }
+}
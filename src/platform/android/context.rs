@@ -1,18 +1,53 @@
+use std::sync::OnceLock;
+
 use crate::platform::android::egl::types::{EGLContext, EGLDisplay};
 
-use super::util::EGL_FUNCTIONS;
+use crate::debug::install_debug_output;
+use crate::DebugFilter;
+
+use super::{
+    current::forget,
+    egl,
+    util::{get_gl_address, EGL_FUNCTIONS},
+};
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Debug)]
 pub struct EglContext {
     pub egl_context: EGLContext,
     pub egl_display: EGLDisplay,
+    /// 这个上下文的 GL 函数表，第一次绑定时加载
+    pub(crate) gl: OnceLock<glow::Context>,
+    /// 调试上下文的消息过滤条件，加载函数表时安装回调
+    pub(crate) debug: Option<DebugFilter>,
+}
+
+impl EglContext {
+    /// GL 函数表；必须在上下文绑定后第一次调用
+    pub(crate) fn glow(&self) -> &glow::Context {
+        self.gl.get_or_init(|| {
+            let mut gl = unsafe { glow::Context::from_loader_function(get_gl_address) };
+            if let Some(filter) = &self.debug {
+                install_debug_output(&mut gl, filter.clone());
+            }
+            gl
+        })
+    }
 }
 
+impl PartialEq for EglContext {
+    fn eq(&self, other: &Self) -> bool {
+        self.egl_context == other.egl_context && self.egl_display == other.egl_display
+    }
+}
+
+impl Eq for EglContext {}
+
 unsafe impl Sync for EglContext {}
 unsafe impl Send for EglContext {}
 
 impl Drop for EglContext {
     fn drop(&mut self) {
+        forget(egl::NO_DISPLAY, egl::NO_SURFACE, self.egl_context);
         let egl = &EGL_FUNCTIONS.0;
         let _ = unsafe { egl.DestroyContext(self.egl_display, self.egl_context) };
     }
@@ -0,0 +1,76 @@
+use std::cell::Cell;
+
+use super::{
+    egl::{
+        self,
+        types::{EGLContext, EGLDisplay, EGLSurface},
+        EGLint,
+    },
+    util::EGL_FUNCTIONS,
+};
+
+/// 一个线程上的 eglMakeCurrent 绑定
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct EglBinding {
+    pub(crate) display: EGLDisplay,
+    pub(crate) draw: EGLSurface,
+    pub(crate) read: EGLSurface,
+    pub(crate) context: EGLContext,
+}
+
+impl EglBinding {
+    /// 没有绑定
+    pub(crate) const NONE: Self = Self {
+        display: egl::NO_DISPLAY,
+        draw: egl::NO_SURFACE,
+        read: egl::NO_SURFACE,
+        context: egl::NO_CONTEXT,
+    };
+}
+
+thread_local! {
+    /// 当前线程上通过本库绑定的表面和上下文，用于跳过重复的 eglMakeCurrent
+    static CURRENT: Cell<EglBinding> = const { Cell::new(EglBinding::NONE) };
+}
+
+/// 当前线程的绑定
+///
+/// 记录的绑定先和 eglGetCurrent* 的结果核对：其他库（如 Swappy）直接调用 eglMakeCurrent 之后记录会失效，
+/// 这时以 EGL 的结果为准并更新记录。这几个函数只读 EGL 的线程状态，不进入驱动。
+pub(crate) fn current() -> EglBinding {
+    let egl = &EGL_FUNCTIONS.0;
+    let actual = unsafe {
+        EglBinding {
+            display: egl.GetCurrentDisplay(),
+            draw: egl.GetCurrentSurface(egl::DRAW as EGLint),
+            read: egl.GetCurrentSurface(egl::READ as EGLint),
+            context: egl.GetCurrentContext(),
+        }
+    };
+    CURRENT.with(|current| {
+        if current.get() != actual {
+            log::debug!("EGL 绑定在本库之外被修改: {:?} -> {:?}", current.get(), actual);
+            current.set(actual);
+        }
+    });
+    actual
+}
+
+/// eglMakeCurrent 成功后记录
+#[inline]
+pub(crate) fn set_current(binding: EglBinding) {
+    CURRENT.with(|current| current.set(binding));
+}
+
+/// 表面、上下文或显示连接释放时调用，避免之后同样的句柄值被误认为已经绑定
+pub(crate) fn forget(display: EGLDisplay, surface: EGLSurface, context: EGLContext) {
+    CURRENT.with(|current| {
+        let binding = current.get();
+        let released = (display != egl::NO_DISPLAY && binding.display == display)
+            || (surface != egl::NO_SURFACE && (binding.draw == surface || binding.read == surface))
+            || (context != egl::NO_CONTEXT && binding.context == context);
+        if released {
+            current.set(EglBinding::NONE);
+        }
+    });
+}
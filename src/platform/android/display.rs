@@ -0,0 +1,379 @@
+use std::ffi::CStr;
+use std::os::raw::c_void;
+
+use raw_window_handle::RawDisplayHandle;
+
+use super::{
+    egl::{
+        self,
+        types::{EGLAttrib, EGLDeviceEXT, EGLDisplay, EGLenum},
+        EGLint,
+    },
+    util::{egl_error, EGL_FUNCTIONS},
+    xlib::XlibDisplay,
+};
+use super::adapter::{explicit_adapter, query_adapters, select_adapter};
+use crate::{DisplayPlatform, InstanceError, PowerPreference};
+
+/// EGL_EXT_platform_xcb
+const PLATFORM_XCB_EXT: EGLenum = 0x31DC;
+const PLATFORM_XCB_SCREEN_EXT: EGLAttrib = 0x31DE;
+/// EGL_EXT_explicit_device
+const DEVICE_EXT: EGLAttrib = 0x322C;
+
+lazy_static! {
+    /// 客户端扩展字符串（与显示连接无关）
+    static ref CLIENT_EXTENSIONS: String = unsafe {
+        let egl = &EGL_FUNCTIONS.0;
+        let extensions = egl.QueryString(egl::NO_DISPLAY, egl::EXTENSIONS as EGLint);
+        if extensions.is_null() {
+            // 不支持 EGL_EXT_client_extensions，清掉 EGL_BAD_DISPLAY
+            egl.GetError();
+            String::new()
+        } else {
+            CStr::from_ptr(extensions).to_string_lossy().into_owned()
+        }
+    };
+}
+
+/// 是否支持客户端扩展 `name`
+pub(crate) fn has_client_extension(name: &str) -> bool {
+    CLIENT_EXTENSIONS.split_whitespace().any(|e| e == name)
+}
+
+/// 显示连接是否支持扩展 `name`，显示连接必须已经初始化
+pub(crate) unsafe fn has_display_extension(egl_display: EGLDisplay, name: &str) -> bool {
+    let egl = &EGL_FUNCTIONS.0;
+    let extensions = egl.QueryString(egl_display, egl::EXTENSIONS as EGLint);
+    if extensions.is_null() {
+        egl.GetError();
+        return false;
+    }
+    CStr::from_ptr(extensions)
+        .to_string_lossy()
+        .split_whitespace()
+        .any(|e| e == name)
+}
+
+/// 至少支持 `names` 中的一个客户端扩展
+fn require_client_extension(names: &[&str]) -> Result<(), InstanceError> {
+    if names.iter().any(|name| has_client_extension(name)) {
+        Ok(())
+    } else {
+        Err(InstanceError::RequiredExtensionUnavailable)
+    }
+}
+
+/// 用 eglGetPlatformDisplay 取得显示连接，EGL 1.4 下退回 eglGetPlatformDisplayEXT
+///
+/// `attributes` 以 `EGL_NONE` 结尾。
+pub(crate) unsafe fn get_platform_display(
+    platform: EGLenum,
+    native_display: *mut c_void,
+    attributes: &[EGLAttrib],
+) -> Result<EGLDisplay, InstanceError> {
+    let egl = &EGL_FUNCTIONS.0;
+
+    let egl_display = if egl.GetPlatformDisplay.is_loaded() {
+        egl.GetPlatformDisplay(platform, native_display, attributes.as_ptr())
+    } else if egl.GetPlatformDisplayEXT.is_loaded() {
+        let attributes: Vec<EGLint> = attributes.iter().map(|v| *v as EGLint).collect();
+        egl.GetPlatformDisplayEXT(platform, native_display, attributes.as_ptr())
+    } else {
+        return Err(InstanceError::RequiredExtensionUnavailable);
+    };
+
+    if egl_display == egl::NO_DISPLAY {
+        return Err(egl_error("eglGetPlatformDisplay"));
+    }
+    Ok(egl_display)
+}
+
+/// 窗口系统平台上按 `power` 选择的设备（EGL_EXT_explicit_device）
+///
+/// 驱动不支持、无法枚举设备或硬件设备不超过一个时返回 `None`，由驱动选择默认设备。
+unsafe fn explicit_device(power: &PowerPreference) -> Option<EGLDeviceEXT> {
+    if !has_client_extension("EGL_EXT_explicit_device") {
+        return None;
+    }
+    let adapters = query_adapters().ok()?;
+    let infos: Vec<_> = adapters.iter().map(|(_, info)| info.clone()).collect();
+    explicit_adapter(&infos, power).map(|index| adapters[index].0)
+}
+
+/// 窗口系统平台的显示连接，有多个显卡时按 `power` 指定设备
+///
+/// `attributes` 不含结尾的 `EGL_NONE`；驱动拒绝指定的设备时退回默认设备。
+unsafe fn window_system_display(
+    platform: EGLenum,
+    native_display: *mut c_void,
+    attributes: &[EGLAttrib],
+    power: &PowerPreference,
+) -> Result<EGLDisplay, InstanceError> {
+    let mut attributes = attributes.to_vec();
+    if let Some(device) = explicit_device(power) {
+        let mut explicit = attributes.clone();
+        explicit.extend_from_slice(&[DEVICE_EXT, device as EGLAttrib, egl::NONE as EGLAttrib]);
+        match get_platform_display(platform, native_display, &explicit) {
+            Ok(egl_display) => return Ok(egl_display),
+            Err(err) => log::info!("EGL 无法指定设备 ({})，使用默认设备", err),
+        }
+    }
+    attributes.push(egl::NONE as EGLAttrib);
+    get_platform_display(platform, native_display, &attributes)
+}
+
+/// 所有 EGL 设备（EGL_EXT_device_enumeration）
+pub(crate) unsafe fn query_devices() -> Result<Vec<EGLDeviceEXT>, InstanceError> {
+    let egl = &EGL_FUNCTIONS.0;
+    if !has_client_extension("EGL_EXT_device_enumeration") || !egl.QueryDevicesEXT.is_loaded() {
+        return Err(InstanceError::RequiredExtensionUnavailable);
+    }
+
+    let mut count = 0;
+    if egl.QueryDevicesEXT(0, std::ptr::null_mut(), &mut count) == egl::FALSE {
+        return Err(egl_error("eglQueryDevicesEXT"));
+    }
+    let mut devices = vec![egl::NO_DEVICE_EXT; count as usize];
+    if egl.QueryDevicesEXT(count, devices.as_mut_ptr(), &mut count) == egl::FALSE {
+        return Err(egl_error("eglQueryDevicesEXT"));
+    }
+    devices.truncate(count as usize);
+    Ok(devices)
+}
+
+/// 设备平台（EGL_EXT_platform_device）上的显示连接
+pub(crate) unsafe fn device_display(device: EGLDeviceEXT) -> Result<EGLDisplay, InstanceError> {
+    require_client_extension(&["EGL_EXT_platform_device"])?;
+    let attributes = [egl::NONE as EGLAttrib];
+    get_platform_display(egl::PLATFORM_DEVICE_EXT, device as *mut c_void, &attributes)
+}
+
+/// 按平台取得显示连接；窗口系统平台连接默认的服务器
+///
+/// 设备平台按 `power` 选择设备；其他平台在支持 EGL_EXT_explicit_device 并且有多个显卡时，
+/// 也按 `power` 指定设备。
+pub(crate) unsafe fn platform_display(
+    platform: DisplayPlatform,
+    power: &PowerPreference,
+) -> Result<EGLDisplay, InstanceError> {
+    let (extensions, egl_platform, native_display): (&[&str], _, _) = match platform {
+        DisplayPlatform::Surfaceless => (
+            &["EGL_MESA_platform_surfaceless"],
+            egl::PLATFORM_SURFACELESS_MESA,
+            egl::DEFAULT_DISPLAY as *mut c_void,
+        ),
+        DisplayPlatform::Device => {
+            let adapters = query_adapters()?;
+            let infos: Vec<_> = adapters.iter().map(|(_, info)| info.clone()).collect();
+            return match select_adapter(&infos, power) {
+                Some(index) => device_display(adapters[index].0),
+                None => Err(InstanceError::egl(
+                    "eglQueryDevicesEXT",
+                    egl::BAD_DEVICE_EXT as EGLint,
+                )),
+            };
+        }
+        DisplayPlatform::Gbm => (
+            &["EGL_KHR_platform_gbm", "EGL_MESA_platform_gbm"],
+            egl::PLATFORM_GBM_KHR,
+            egl::DEFAULT_DISPLAY as *mut c_void,
+        ),
+        DisplayPlatform::X11 => (
+            &["EGL_KHR_platform_x11", "EGL_EXT_platform_x11"],
+            egl::PLATFORM_X11_KHR,
+            egl::DEFAULT_DISPLAY as *mut c_void,
+        ),
+        DisplayPlatform::Wayland => (
+            &["EGL_KHR_platform_wayland", "EGL_EXT_platform_wayland"],
+            egl::PLATFORM_WAYLAND_KHR,
+            egl::DEFAULT_DISPLAY as *mut c_void,
+        ),
+    };
+
+    require_client_extension(extensions)?;
+    window_system_display(egl_platform, native_display, &[], power)
+}
+
+/// `Instance::new` 使用的显示连接和它的平台
+///
+/// Linux 下支持 EGL_EXT_platform_base 和 EGL_EXT_explicit_device 时，用 eglGetDisplay 默认的窗口系统
+/// （设置了 DISPLAY 时为 X11，否则为 WAYLAND_DISPLAY 的 Wayland）取得平台显示连接，有多个显卡时按 `power`
+/// 指定设备；不支持这两个扩展、没有窗口系统或平台不可用时退回 eglGetDisplay(EGL_DEFAULT_DISPLAY)，
+/// 这时平台由驱动决定，返回 `None`。
+pub(crate) unsafe fn default_display(
+    power: &PowerPreference,
+) -> Result<(EGLDisplay, Option<DisplayPlatform>), InstanceError> {
+    #[cfg(not(target_os = "android"))]
+    {
+        if !has_client_extension("EGL_EXT_platform_base")
+            || !has_client_extension("EGL_EXT_explicit_device")
+        {
+            log::info!(
+                "EGL 不支持 EGL_EXT_platform_base / EGL_EXT_explicit_device，使用 eglGetDisplay，无法按 {:?} 选择显卡",
+                power
+            );
+        } else if let Some(platform) = default_platform() {
+            match platform_display(platform, power) {
+                Ok(egl_display) => return Ok((egl_display, Some(platform))),
+                Err(err) => {
+                    log::info!("EGL 平台 {:?} 不可用 ({})，使用 eglGetDisplay", platform, err)
+                }
+            }
+        } else {
+            log::info!("没有设置 DISPLAY 或 WAYLAND_DISPLAY，使用 eglGetDisplay");
+        }
+    }
+
+    let egl = &EGL_FUNCTIONS.0;
+    let egl_display = egl.GetDisplay(egl::DEFAULT_DISPLAY);
+    if egl_display == egl::NO_DISPLAY {
+        return Err(egl_error("eglGetDisplay"));
+    }
+    Ok((egl_display, None))
+}
+
+/// eglGetDisplay(EGL_DEFAULT_DISPLAY) 默认使用的窗口系统平台，和 Mesa 一样 X11 优先
+#[cfg(not(target_os = "android"))]
+fn default_platform() -> Option<DisplayPlatform> {
+    if std::env::var_os("DISPLAY").is_some() {
+        Some(DisplayPlatform::X11)
+    } else if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+        Some(DisplayPlatform::Wayland)
+    } else {
+        None
+    }
+}
+
+/// X11 平台上的显示连接
+unsafe fn x11_display(
+    native_display: *mut c_void,
+    screen: i32,
+    power: &PowerPreference,
+) -> Result<EGLDisplay, InstanceError> {
+    require_client_extension(&["EGL_KHR_platform_x11", "EGL_EXT_platform_x11"])?;
+    let attributes = [egl::PLATFORM_X11_SCREEN_KHR as EGLAttrib, screen as EGLAttrib];
+    window_system_display(egl::PLATFORM_X11_KHR, native_display, &attributes, power)
+}
+
+/// 窗口系统的显示句柄对应的显示连接，有多个显卡时按 `power` 指定设备（见 `platform_display`）
+///
+/// XCB 句柄在驱动不支持 EGL_EXT_platform_xcb 时，另开一个 Xlib 连接走 X11 平台，
+/// 这个连接随第二个返回值返回，必须在 eglTerminate 之后释放。
+///
+/// # 返回值
+/// - 不支持的窗口系统返回 `InstanceError::IncompatibleWindowHandle`。
+pub(crate) unsafe fn handle_display(
+    handle: &RawDisplayHandle,
+    power: &PowerPreference,
+) -> Result<(EGLDisplay, Option<XlibDisplay>), InstanceError> {
+    match handle {
+        RawDisplayHandle::Android(_) => {
+            let egl = &EGL_FUNCTIONS.0;
+            let egl_display = egl.GetDisplay(egl::DEFAULT_DISPLAY);
+            if egl_display == egl::NO_DISPLAY {
+                return Err(egl_error("eglGetDisplay"));
+            }
+            Ok((egl_display, None))
+        }
+        RawDisplayHandle::Xlib(handle) => {
+            let native_display = handle
+                .display
+                .map_or(egl::DEFAULT_DISPLAY as *mut c_void, |display| display.as_ptr());
+            Ok((x11_display(native_display, handle.screen, power)?, None))
+        }
+        RawDisplayHandle::Xcb(handle) if has_client_extension("EGL_EXT_platform_xcb") => {
+            let native_display = handle
+                .connection
+                .map_or(egl::DEFAULT_DISPLAY as *mut c_void, |connection| connection.as_ptr());
+            let attributes = [PLATFORM_XCB_SCREEN_EXT, handle.screen as EGLAttrib];
+            let egl_display =
+                window_system_display(PLATFORM_XCB_EXT, native_display, &attributes, power)?;
+            Ok((egl_display, None))
+        }
+        RawDisplayHandle::Xcb(handle) => {
+            // Xlib-xcb 桥接：按 DISPLAY 连接同一个 X 服务器，窗口 ID 通用；
+            // DISPLAY 指向其他服务器时返回 IncompatibleWindowHandle
+            log::info!("EGL 不支持 EGL_EXT_platform_xcb，XCB 窗口改用 Xlib 连接");
+            let xlib_display = match handle.connection {
+                Some(connection) => XlibDisplay::open_for_xcb(connection.as_ptr())?,
+                None => XlibDisplay::open()?,
+            };
+            let egl_display = x11_display(xlib_display.as_ptr(), handle.screen, power)?;
+            Ok((egl_display, Some(xlib_display)))
+        }
+        RawDisplayHandle::Wayland(handle) => {
+            require_client_extension(&["EGL_KHR_platform_wayland", "EGL_EXT_platform_wayland"])?;
+            let egl_display = window_system_display(
+                egl::PLATFORM_WAYLAND_KHR,
+                handle.display.as_ptr(),
+                &[],
+                power,
+            )?;
+            Ok((egl_display, None))
+        }
+        _ => Err(InstanceError::IncompatibleWindowHandle),
+    }
+}
+
+/// `platform` 平台上的显示连接能否使用 `handle` 的窗口
+///
+/// surfaceless 和设备平台没有窗口系统；GBM 平台的窗口是 gbm_surface，不是 raw-window-handle 的窗口。
+pub(crate) fn platform_accepts(platform: DisplayPlatform, handle: &RawDisplayHandle) -> bool {
+    match platform {
+        DisplayPlatform::Surfaceless | DisplayPlatform::Device | DisplayPlatform::Gbm => false,
+        DisplayPlatform::X11 => {
+            matches!(handle, RawDisplayHandle::Xlib(_) | RawDisplayHandle::Xcb(_))
+        }
+        DisplayPlatform::Wayland => matches!(handle, RawDisplayHandle::Wayland(_)),
+    }
+}
+
+/// 两个显示句柄是否是同一个连接
+pub(crate) fn same_display(a: &RawDisplayHandle, b: &RawDisplayHandle) -> bool {
+    match (a, b) {
+        (RawDisplayHandle::Android(_), RawDisplayHandle::Android(_)) => true,
+        (RawDisplayHandle::Xlib(a), RawDisplayHandle::Xlib(b)) => a.display == b.display,
+        (RawDisplayHandle::Xcb(a), RawDisplayHandle::Xcb(b)) => a.connection == b.connection,
+        (RawDisplayHandle::Wayland(a), RawDisplayHandle::Wayland(b)) => a.display == b.display,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ptr::NonNull;
+
+    use raw_window_handle::{
+        AndroidDisplayHandle, WaylandDisplayHandle, XcbDisplayHandle, XlibDisplayHandle,
+    };
+
+    use super::*;
+
+    #[test]
+    fn platform_accepts_windows() {
+        let mut wl_display = 0u8;
+        let xlib = RawDisplayHandle::Xlib(XlibDisplayHandle::new(None, 0));
+        let xcb = RawDisplayHandle::Xcb(XcbDisplayHandle::new(None, 0));
+        let wayland = RawDisplayHandle::Wayland(WaylandDisplayHandle::new(
+            NonNull::from(&mut wl_display).cast(),
+        ));
+        let android = RawDisplayHandle::Android(AndroidDisplayHandle::new());
+        let handles = [xlib, xcb, wayland, android];
+
+        // (平台, 依次能否使用 Xlib / XCB / Wayland / Android 的窗口)
+        let cases = [
+            (DisplayPlatform::Surfaceless, [false, false, false, false]),
+            (DisplayPlatform::Device, [false, false, false, false]),
+            (DisplayPlatform::Gbm, [false, false, false, false]),
+            (DisplayPlatform::X11, [true, true, false, false]),
+            (DisplayPlatform::Wayland, [false, false, true, false]),
+        ];
+        for (platform, expected) in cases {
+            for (handle, expected) in handles.iter().zip(expected) {
+                let accepts = platform_accepts(platform, handle);
+                assert_eq!(accepts, expected, "{:?} {:?}", platform, handle);
+            }
+        }
+    }
+}
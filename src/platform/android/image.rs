@@ -0,0 +1,431 @@
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+use std::os::raw::c_void;
+
+use super::{
+    display::has_display_extension,
+    egl::{
+        self,
+        types::{EGLAttrib, EGLClientBuffer, EGLContext, EGLDisplay, EGLImage, EGLenum},
+        EGLint,
+    },
+    util::{egl_error, get_gl_address, EGL_FUNCTIONS},
+};
+use crate::{DmabufDesc, DmabufModifier, ExportedDmabuf, ExportedDmabufPlane, InstanceError};
+
+/// GL_OES_EGL_image_external 的纹理目标
+pub(crate) const TEXTURE_EXTERNAL_OES: u32 = 0x8D65;
+
+/// 各平面的属性名：fd、偏移、行距、修饰符低 32 位、修饰符高 32 位
+const DMABUF_PLANE_ATTRIBUTES: [[EGLenum; 5]; 4] = [
+    [
+        egl::DMA_BUF_PLANE0_FD_EXT,
+        egl::DMA_BUF_PLANE0_OFFSET_EXT,
+        egl::DMA_BUF_PLANE0_PITCH_EXT,
+        egl::DMA_BUF_PLANE0_MODIFIER_LO_EXT,
+        egl::DMA_BUF_PLANE0_MODIFIER_HI_EXT,
+    ],
+    [
+        egl::DMA_BUF_PLANE1_FD_EXT,
+        egl::DMA_BUF_PLANE1_OFFSET_EXT,
+        egl::DMA_BUF_PLANE1_PITCH_EXT,
+        egl::DMA_BUF_PLANE1_MODIFIER_LO_EXT,
+        egl::DMA_BUF_PLANE1_MODIFIER_HI_EXT,
+    ],
+    [
+        egl::DMA_BUF_PLANE2_FD_EXT,
+        egl::DMA_BUF_PLANE2_OFFSET_EXT,
+        egl::DMA_BUF_PLANE2_PITCH_EXT,
+        egl::DMA_BUF_PLANE2_MODIFIER_LO_EXT,
+        egl::DMA_BUF_PLANE2_MODIFIER_HI_EXT,
+    ],
+    [
+        egl::DMA_BUF_PLANE3_FD_EXT,
+        egl::DMA_BUF_PLANE3_OFFSET_EXT,
+        egl::DMA_BUF_PLANE3_PITCH_EXT,
+        egl::DMA_BUF_PLANE3_MODIFIER_LO_EXT,
+        egl::DMA_BUF_PLANE3_MODIFIER_HI_EXT,
+    ],
+];
+
+type ImageTargetTexture2DFn = unsafe extern "system" fn(target: u32, image: *const c_void);
+
+lazy_static! {
+    /// glEGLImageTargetTexture2DOES（GL_OES_EGL_image），与上下文无关
+    static ref IMAGE_TARGET_TEXTURE_2D: Option<ImageTargetTexture2DFn> = unsafe {
+        let function = get_gl_address("glEGLImageTargetTexture2DOES");
+        if function.is_null() {
+            None
+        } else {
+            Some(std::mem::transmute::<*const c_void, ImageTargetTexture2DFn>(function))
+        }
+    };
+}
+
+/// EGLImage，可以在不同共享组的上下文之间共享
+///
+/// EGL 1.5 下使用核心函数，EGL 1.4 下使用 EGL_KHR_image_base。
+#[derive(Debug)]
+pub struct EglImage {
+    pub(crate) egl_image: EGLImage,
+    pub(crate) egl_display: EGLDisplay,
+    /// 由 eglCreateImageKHR 创建，之后也只能用 KHR 函数
+    khr: bool,
+}
+
+unsafe impl Sync for EglImage {}
+unsafe impl Send for EglImage {}
+
+impl EglImage {
+    /// 由上下文中的 2D 纹理创建（EGL_GL_TEXTURE_2D）。
+    ///
+    /// # 参数
+    /// - `core`: 显示连接的版本不低于 EGL 1.5，使用核心函数。
+    /// - `texture`: `egl_context` 中的纹理名，`level` 层必须已经定义。
+    pub(crate) unsafe fn from_texture(
+        egl_display: EGLDisplay,
+        core: bool,
+        egl_context: EGLContext,
+        texture: u32,
+        level: i32,
+    ) -> Result<Self, InstanceError> {
+        if !core && !has_display_extension(egl_display, "EGL_KHR_gl_texture_2D_image") {
+            return Err(InstanceError::RequiredExtensionUnavailable);
+        }
+
+        let attributes = [
+            egl::GL_TEXTURE_LEVEL as EGLAttrib,
+            level as EGLAttrib,
+            egl::NONE as EGLAttrib,
+        ];
+        Self::create(
+            egl_display,
+            core,
+            egl_context,
+            egl::GL_TEXTURE_2D,
+            texture as usize as EGLClientBuffer,
+            &attributes,
+        )
+    }
+
+    /// 由上下文中的渲染缓冲创建（EGL_GL_RENDERBUFFER）。
+    ///
+    /// # 参数
+    /// - `core`: 显示连接的版本不低于 EGL 1.5，使用核心函数。
+    /// - `renderbuffer`: `egl_context` 中的渲染缓冲名，存储必须已经分配。
+    pub(crate) unsafe fn from_renderbuffer(
+        egl_display: EGLDisplay,
+        core: bool,
+        egl_context: EGLContext,
+        renderbuffer: u32,
+    ) -> Result<Self, InstanceError> {
+        if !core && !has_display_extension(egl_display, "EGL_KHR_gl_renderbuffer_image") {
+            return Err(InstanceError::RequiredExtensionUnavailable);
+        }
+
+        let attributes = [egl::NONE as EGLAttrib];
+        Self::create(
+            egl_display,
+            core,
+            egl_context,
+            egl::GL_RENDERBUFFER,
+            renderbuffer as usize as EGLClientBuffer,
+            &attributes,
+        )
+    }
+
+    /// 导入 DMA-BUF（EGL_LINUX_DMA_BUF_EXT）。
+    ///
+    /// EGL 不接管 fd，导入后 fd 可以关闭。
+    ///
+    /// # 返回值
+    /// - 不支持 EGL_EXT_image_dma_buf_import，或指定了修饰符而不支持
+    ///   EGL_EXT_image_dma_buf_import_modifiers 时返回 `InstanceError::RequiredExtensionUnavailable`。
+    /// - 平面数不在 1 到 4 之间时返回 `EGL_BAD_PARAMETER`。
+    pub(crate) unsafe fn from_dmabuf(
+        egl_display: EGLDisplay,
+        core: bool,
+        desc: &DmabufDesc,
+    ) -> Result<Self, InstanceError> {
+        if !has_display_extension(egl_display, "EGL_EXT_image_dma_buf_import") {
+            return Err(InstanceError::RequiredExtensionUnavailable);
+        }
+        if desc.planes.is_empty() || desc.planes.len() > DMABUF_PLANE_ATTRIBUTES.len() {
+            return Err(InstanceError::egl("eglCreateImage", egl::BAD_PARAMETER as EGLint));
+        }
+        if desc.planes.iter().any(|plane| plane.modifier.is_some())
+            && !has_display_extension(egl_display, "EGL_EXT_image_dma_buf_import_modifiers")
+        {
+            return Err(InstanceError::RequiredExtensionUnavailable);
+        }
+
+        let mut attributes = vec![
+            egl::WIDTH as EGLAttrib,
+            desc.width as EGLAttrib,
+            egl::HEIGHT as EGLAttrib,
+            desc.height as EGLAttrib,
+            egl::LINUX_DRM_FOURCC_EXT as EGLAttrib,
+            desc.fourcc as EGLAttrib,
+        ];
+        for (plane, names) in desc.planes.iter().zip(DMABUF_PLANE_ATTRIBUTES.iter()) {
+            attributes.extend_from_slice(&[
+                names[0] as EGLAttrib,
+                plane.fd.as_raw_fd() as EGLAttrib,
+                names[1] as EGLAttrib,
+                plane.offset as EGLAttrib,
+                names[2] as EGLAttrib,
+                plane.stride as EGLAttrib,
+            ]);
+            if let Some(modifier) = plane.modifier {
+                attributes.extend_from_slice(&[
+                    names[3] as EGLAttrib,
+                    (modifier & 0xFFFF_FFFF) as EGLAttrib,
+                    names[4] as EGLAttrib,
+                    (modifier >> 32) as EGLAttrib,
+                ]);
+            }
+        }
+        attributes.push(egl::NONE as EGLAttrib);
+
+        Self::create(
+            egl_display,
+            core,
+            egl::NO_CONTEXT,
+            egl::LINUX_DMA_BUF_EXT,
+            std::ptr::null(),
+            &attributes,
+        )
+    }
+
+    /// eglCreateImage，不能使用时退回 eglCreateImageKHR；`attributes` 以 `EGL_NONE` 结尾
+    pub(crate) unsafe fn create(
+        egl_display: EGLDisplay,
+        core: bool,
+        egl_context: EGLContext,
+        target: EGLenum,
+        buffer: EGLClientBuffer,
+        attributes: &[EGLAttrib],
+    ) -> Result<Self, InstanceError> {
+        let egl = &EGL_FUNCTIONS.0;
+
+        if core && egl.CreateImage.is_loaded() {
+            let egl_image =
+                egl.CreateImage(egl_display, egl_context, target, buffer, attributes.as_ptr());
+            if egl_image == egl::NO_IMAGE {
+                return Err(egl_error("eglCreateImage"));
+            }
+            return Ok(Self {
+                egl_image,
+                egl_display,
+                khr: false,
+            });
+        }
+
+        if !has_display_extension(egl_display, "EGL_KHR_image_base") || !egl.CreateImageKHR.is_loaded()
+        {
+            return Err(InstanceError::RequiredExtensionUnavailable);
+        }
+        let attributes: Vec<EGLint> = attributes.iter().map(|v| *v as EGLint).collect();
+        let egl_image =
+            egl.CreateImageKHR(egl_display, egl_context, target, buffer, attributes.as_ptr());
+        if egl_image == egl::NO_IMAGE_KHR {
+            return Err(egl_error("eglCreateImageKHR"));
+        }
+        Ok(Self {
+            egl_image,
+            egl_display,
+            khr: true,
+        })
+    }
+
+    /// 导出为 DMA-BUF（EGL_MESA_image_dma_buf_export），每次调用导出新的 fd。
+    ///
+    /// 驱动只给出部分平面的 fd 时（其余平面和前一个平面在同一个 DMA-BUF 中），复制前一个平面的 fd。
+    pub(crate) fn export_dmabuf(&self) -> Result<ExportedDmabuf, InstanceError> {
+        let egl = &EGL_FUNCTIONS.0;
+        unsafe {
+            if !has_display_extension(self.egl_display, "EGL_MESA_image_dma_buf_export")
+                || !egl.ExportDMABUFImageQueryMESA.is_loaded()
+                || !egl.ExportDMABUFImageMESA.is_loaded()
+            {
+                return Err(InstanceError::RequiredExtensionUnavailable);
+            }
+
+            let (mut fourcc, mut plane_count) = (0, 0);
+            if egl.ExportDMABUFImageQueryMESA(
+                self.egl_display,
+                self.egl_image,
+                &mut fourcc,
+                &mut plane_count,
+                std::ptr::null_mut(),
+            ) == egl::FALSE
+            {
+                return Err(egl_error("eglExportDMABUFImageQueryMESA"));
+            }
+            let mut modifiers = vec![0; plane_count.max(0) as usize];
+            if egl.ExportDMABUFImageQueryMESA(
+                self.egl_display,
+                self.egl_image,
+                &mut fourcc,
+                &mut plane_count,
+                modifiers.as_mut_ptr(),
+            ) == egl::FALSE
+            {
+                return Err(egl_error("eglExportDMABUFImageQueryMESA"));
+            }
+
+            let plane_count = modifiers.len();
+            let mut fds = vec![-1; plane_count];
+            let mut strides = vec![0; plane_count];
+            let mut offsets = vec![0; plane_count];
+            if egl.ExportDMABUFImageMESA(
+                self.egl_display,
+                self.egl_image,
+                fds.as_mut_ptr(),
+                strides.as_mut_ptr(),
+                offsets.as_mut_ptr(),
+            ) == egl::FALSE
+            {
+                return Err(egl_error("eglExportDMABUFImageMESA"));
+            }
+
+            // 先全部接管，出错返回时关闭
+            let fds: Vec<Option<OwnedFd>> = fds
+                .into_iter()
+                .map(|fd| (fd >= 0).then(|| OwnedFd::from_raw_fd(fd)))
+                .collect();
+            let mut planes: Vec<ExportedDmabufPlane> = Vec::with_capacity(plane_count);
+            for (i, fd) in fds.into_iter().enumerate() {
+                let fd = match (fd, planes.last()) {
+                    (Some(fd), _) => fd,
+                    (None, Some(previous)) => previous.fd.try_clone().map_err(|_| {
+                        InstanceError::egl("eglExportDMABUFImageMESA", egl::BAD_ALLOC as EGLint)
+                    })?,
+                    (None, None) => {
+                        return Err(InstanceError::egl(
+                            "eglExportDMABUFImageMESA",
+                            egl::BAD_MATCH as EGLint,
+                        ))
+                    }
+                };
+                planes.push(ExportedDmabufPlane {
+                    fd,
+                    offset: offsets[i] as u32,
+                    stride: strides[i] as u32,
+                });
+            }
+
+            Ok(ExportedDmabuf {
+                fourcc: fourcc as u32,
+                modifier: modifiers.first().copied().unwrap_or(0),
+                planes,
+            })
+        }
+    }
+
+    /// 把图像指定为当前上下文中绑定在 `target` 上的纹理的存储（glEGLImageTargetTexture2DOES）。
+    ///
+    /// # 参数
+    /// - `target`: `GL_TEXTURE_2D` 或 `TEXTURE_EXTERNAL_OES`，调用者需要确认上下文支持对应的扩展。
+    pub(crate) fn bind_to_texture(&self, target: u32) -> Result<(), InstanceError> {
+        match IMAGE_TARGET_TEXTURE_2D.as_ref() {
+            Some(function) => {
+                unsafe { function(target, self.egl_image) };
+                Ok(())
+            }
+            None => Err(InstanceError::RequiredExtensionUnavailable),
+        }
+    }
+}
+
+/// 可以导入的 DMA-BUF 格式（DRM fourcc），需要 EGL_EXT_image_dma_buf_import_modifiers
+pub(crate) unsafe fn dmabuf_formats(egl_display: EGLDisplay) -> Result<Vec<u32>, InstanceError> {
+    let egl = &EGL_FUNCTIONS.0;
+    if !has_display_extension(egl_display, "EGL_EXT_image_dma_buf_import_modifiers")
+        || !egl.QueryDmaBufFormatsEXT.is_loaded()
+    {
+        return Err(InstanceError::RequiredExtensionUnavailable);
+    }
+
+    let mut count = 0;
+    if egl.QueryDmaBufFormatsEXT(egl_display, 0, std::ptr::null_mut(), &mut count) == egl::FALSE {
+        return Err(egl_error("eglQueryDmaBufFormatsEXT"));
+    }
+    let mut formats = vec![0; count as usize];
+    if egl.QueryDmaBufFormatsEXT(egl_display, count, formats.as_mut_ptr(), &mut count) == egl::FALSE
+    {
+        return Err(egl_error("eglQueryDmaBufFormatsEXT"));
+    }
+    formats.truncate(count as usize);
+    Ok(formats.into_iter().map(|format| format as u32).collect())
+}
+
+/// 格式 `fourcc` 可以导入的修饰符，需要 EGL_EXT_image_dma_buf_import_modifiers
+pub(crate) unsafe fn dmabuf_modifiers(
+    egl_display: EGLDisplay,
+    fourcc: u32,
+) -> Result<Vec<DmabufModifier>, InstanceError> {
+    let egl = &EGL_FUNCTIONS.0;
+    if !has_display_extension(egl_display, "EGL_EXT_image_dma_buf_import_modifiers")
+        || !egl.QueryDmaBufModifiersEXT.is_loaded()
+    {
+        return Err(InstanceError::RequiredExtensionUnavailable);
+    }
+
+    let format = fourcc as EGLint;
+    let mut count = 0;
+    if egl.QueryDmaBufModifiersEXT(
+        egl_display,
+        format,
+        0,
+        std::ptr::null_mut(),
+        std::ptr::null_mut(),
+        &mut count,
+    ) == egl::FALSE
+    {
+        return Err(egl_error("eglQueryDmaBufModifiersEXT"));
+    }
+    let mut modifiers = vec![0; count as usize];
+    let mut external_only = vec![egl::FALSE; count as usize];
+    if egl.QueryDmaBufModifiersEXT(
+        egl_display,
+        format,
+        count,
+        modifiers.as_mut_ptr(),
+        external_only.as_mut_ptr(),
+        &mut count,
+    ) == egl::FALSE
+    {
+        return Err(egl_error("eglQueryDmaBufModifiersEXT"));
+    }
+
+    Ok(modifiers
+        .into_iter()
+        .zip(external_only)
+        .take(count as usize)
+        .map(|(modifier, external_only)| DmabufModifier {
+            modifier,
+            external_only: external_only != egl::FALSE,
+        })
+        .collect())
+}
+
+impl PartialEq for EglImage {
+    fn eq(&self, other: &Self) -> bool {
+        self.egl_image == other.egl_image && self.egl_display == other.egl_display
+    }
+}
+
+impl Eq for EglImage {}
+
+impl Drop for EglImage {
+    fn drop(&mut self) {
+        let egl = &EGL_FUNCTIONS.0;
+        unsafe {
+            if self.khr {
+                egl.DestroyImageKHR(self.egl_display, self.egl_image);
+            } else {
+                egl.DestroyImage(self.egl_display, self.egl_image);
+            }
+        }
+    }
+}
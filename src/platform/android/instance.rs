@@ -1,22 +1,52 @@
-#[allow(deprecated)]
-use raw_window_handle::{HasRawDisplayHandle, HasRawWindowHandle, RawWindowHandle, XlibWindowHandle};
+use raw_window_handle::{
+    HasDisplayHandle, HasWindowHandle, RawDisplayHandle, RawWindowHandle, XcbWindowHandle,
+    XlibWindowHandle,
+};
 use std::collections::HashMap;
-use std::ffi::CString;
-use std::os::raw::{c_char, c_void};
+use std::os::fd::OwnedFd;
+use std::os::raw::c_void;
+#[cfg(feature = "swappy")]
+use std::sync::atomic::AtomicU64;
+use std::sync::{
+    atomic::{AtomicI32, Ordering},
+    OnceLock,
+};
 
 #[cfg(feature = "swappy")]
 use super::swappy::{
     SwappyGL_destroy, SwappyGL_enableStats, SwappyGL_init, SwappyGL_isEnabled,
-    SwappyGL_setAutoPipelineMode, SwappyGL_setAutoSwapInterval, SwappyGL_setSwapIntervalNS,
-    SwappyGL_setWindow, SwappyGL_swap,
+    SwappyGL_getRefreshPeriodNanos, SwappyGL_setAutoPipelineMode, SwappyGL_setAutoSwapInterval,
+    SwappyGL_setSwapIntervalNS, SwappyGL_setWindow, SwappyGL_swap,
 };
 
-use super::{context::EglContext, surface::EglSurface, util::EGL_FUNCTIONS};
+use super::{
+    context::EglContext,
+    current::{current, forget, set_current, EglBinding},
+    adapter::query_adapters,
+    display::{
+        default_display, device_display, handle_display, has_display_extension, platform_accepts,
+        platform_display, same_display,
+    },
+    image::{dmabuf_formats, dmabuf_modifiers, EglImage},
+    surface::{EglSurface, NativeWindowRef, SWAP_INTERVAL_UNSET},
+    sync::EglFence,
+    util::{egl_error, EGL_FUNCTIONS},
+    wayland::WlEglWindow,
+    xlib::XlibDisplay,
+};
 use crate::{
     platform::android::egl::{self, EGLint},
-    platform::android::{egl::types::EGLDisplay, util::egl_config_from_display},
-    InstanceError, PowerPreference,
+    platform::android::{
+        egl::types::{EGLConfig, EGLDisplay},
+        util::{all_configs, config_info, egl_config_from_display, get_config_attrib},
+    },
+    AdapterInfo, ConfigInfo, ConfigRequest, ContextOptions, DisplayPlatform, DmabufDesc,
+    DmabufModifier, FramePacing, InstanceError, PbufferFormat, PowerPreference, PresentMode,
 };
+/// EGL_KHR_create_context：EGL 1.4 下请求调试上下文
+const CONTEXT_FLAGS_KHR: EGLint = 0x30FC;
+const CONTEXT_OPENGL_DEBUG_BIT_KHR: EGLint = 0x0001;
+
 lazy_static! {
     /// 全局存储EGL表面（surface）的指针，使用读写锁确保线程安全。
     static ref SURFACE_PTR: std::sync::RwLock<u64> = std::sync::RwLock::new(0);
@@ -27,16 +57,21 @@ lazy_static! {
 pub struct EglInstance {
     /// EGL 显示，表示与显示系统的连接。
     display: EGLDisplay,
-    /// OpenGL ES 上下文，用于渲染。
-    context: Option<glow::Context>,
-    /// 是否启用了垂直同步（VSync）。
+    /// eglInitialize 返回的 EGL 版本（主版本, 次版本）。
+    version: (EGLint, EGLint),
+    /// 创建时选定的配置，Surface 和 Context 共用。
+    config: EGLConfig,
+    /// 由窗口系统的显示句柄创建时记录该句柄，用于检查窗口是否属于同一个显示连接。
+    display_handle: Option<RawDisplayHandle>,
+    /// 用 eglGetPlatformDisplay 创建时的平台，用于检查窗口是否属于这个平台；由驱动决定平台时为 `None`。
+    platform: Option<DisplayPlatform>,
+    /// XCB 句柄走 Xlib 桥接时自己打开的 Xlib 连接，eglTerminate 之后关闭。
+    xlib_display: Option<XlibDisplay>,
+    /// 新建表面是否使用垂直同步，之后可以用 `set_present_mode` 修改。
     is_vsync: bool,
-    /// 帧率统计功能，仅在 `fps` 特性启用时存在。
-    #[cfg(feature = "fps")]
-    fps: AtomicU32,
-    /// 时间记录，用于计算帧率，仅在 `fps` 特性启用时存在。
-    #[cfg(feature = "fps")]
-    time: pi_share::ShareCell<std::time::Instant>,
+    /// `set_frame_pacing` 设置的 Swappy 交换间隔（纳秒），0 表示使用屏幕的刷新周期。
+    #[cfg(feature = "swappy")]
+    swappy_interval_ns: AtomicU64,
 }
 
 impl Drop for EglInstance {
@@ -47,8 +82,11 @@ impl Drop for EglInstance {
         unsafe {
             // 终止 EGL 显示连接。
             let result = egl.Terminate(self.display);
-            // 确保终止操作成功。
-            assert_ne!(result, egl::FALSE);
+            forget(self.display, egl::NO_SURFACE, egl::NO_CONTEXT);
+            // Drop 中无法返回错误，只记录日志
+            if result == egl::FALSE {
+                log::error!("{}", egl_error("eglTerminate"));
+            }
             // 重置显示为无效值。
             self.display = egl::NO_DISPLAY;
             // 如果启用了 `swappy` 特性，销毁 SwappyGL。
@@ -63,12 +101,117 @@ impl EglInstance {
     /// 创建新的 EGL 实例。
     ///
     /// # 参数
-    /// - `_power`: 表示电源偏好（未使用，保留供未来扩展）。
+    /// - `power`: 电源偏好；Linux 下有多个显卡并且支持 EGL_EXT_platform_base 和 EGL_EXT_explicit_device 时，
+    ///   在默认窗口系统（X11 / Wayland）的平台显示连接上按它选择设备，否则退回 eglGetDisplay 由驱动决定。
+    ///   不会修改 `DRI_PRIME` 等环境变量。
     /// - `is_vsync`: 是否启用垂直同步（VSync）功能。
+    /// - `config`: 帧缓冲配置的选择条件。
     ///
     /// # 返回值
     /// - `Result<Self, InstanceError>`: 创建成功则返回 `EglInstance` 实例，失败则返回错误信息。
-    pub fn new(_power: PowerPreference, is_vsync: bool) -> Result<Self, InstanceError> {
+    pub fn new(
+        power: PowerPreference,
+        is_vsync: bool,
+        config: &ConfigRequest,
+    ) -> Result<Self, InstanceError> {
+        unsafe {
+            let (egl_display, platform) = default_display(&power)?;
+            let mut instance = Self::from_display(egl_display, is_vsync, config)?;
+            instance.platform = platform;
+            Ok(instance)
+        }
+    }
+
+    /// 在窗口系统的显示连接上创建实例（eglGetPlatformDisplay）。
+    ///
+    /// 之后 `create_surface` 只接受属于这个显示连接的窗口。
+    ///
+    /// # 参数
+    /// - `handle`: Xlib / XCB / Wayland / Android 的显示句柄。
+    /// - `power`: Linux 下有多个显卡并且支持 EGL_EXT_explicit_device 时按它选择设备，否则由驱动决定。
+    pub fn from_display_handle(
+        handle: RawDisplayHandle,
+        power: PowerPreference,
+        is_vsync: bool,
+        config: &ConfigRequest,
+    ) -> Result<Self, InstanceError> {
+        unsafe {
+            let (egl_display, xlib_display) = handle_display(&handle, &power)?;
+            let mut instance = Self::from_display(egl_display, is_vsync, config)?;
+            instance.display_handle = Some(handle);
+            instance.xlib_display = xlib_display;
+            Ok(instance)
+        }
+    }
+
+    /// 列出所有 EGL 设备（EGL_EXT_device_enumeration）。
+    pub fn adapters() -> Result<Vec<AdapterInfo>, InstanceError> {
+        unsafe { Ok(query_adapters()?.into_iter().map(|(_, info)| info).collect()) }
+    }
+
+    /// 在指定设备上创建实例，使用设备平台（EGL_EXT_platform_device），只能创建 pbuffer 表面。
+    ///
+    /// # 参数
+    /// - `id`: `AdapterInfo::id`。
+    pub fn with_adapter(
+        id: usize,
+        is_vsync: bool,
+        config: &ConfigRequest,
+    ) -> Result<Self, InstanceError> {
+        unsafe {
+            let device = match query_adapters()?.get(id) {
+                Some((device, _)) => *device,
+                None => {
+                    return Err(InstanceError::egl(
+                        "eglQueryDevicesEXT",
+                        egl::BAD_DEVICE_EXT as EGLint,
+                    ))
+                }
+            };
+            let egl_display = device_display(device)?;
+            let mut instance = Self::from_display(egl_display, is_vsync, config)?;
+            instance.platform = Some(DisplayPlatform::Device);
+            Ok(instance)
+        }
+    }
+
+    /// 依次尝试 `platforms`，用第一个能初始化的平台创建实例。
+    ///
+    /// # 返回值
+    /// - 全部失败时返回最后一个平台的错误。
+    pub fn with_platforms(
+        platforms: &[DisplayPlatform],
+        power: PowerPreference,
+        is_vsync: bool,
+        config: &ConfigRequest,
+    ) -> Result<Self, InstanceError> {
+        let mut last_err = InstanceError::RequiredExtensionUnavailable;
+        for platform in platforms {
+            let instance = unsafe {
+                platform_display(*platform, &power)
+                    .and_then(|egl_display| Self::from_display(egl_display, is_vsync, config))
+            };
+            match instance {
+                Ok(mut instance) => {
+                    log::info!("EGL 使用平台 {:?}", platform);
+                    instance.platform = Some(*platform);
+                    return Ok(instance);
+                }
+                Err(err) => {
+                    log::info!("EGL 平台 {:?} 不可用: {}", platform, err);
+                    last_err = err;
+                }
+            }
+        }
+        Err(last_err)
+    }
+
+    /// 初始化显示连接，选定配置。
+    unsafe fn from_display(
+        egl_display: EGLDisplay,
+        is_vsync: bool,
+        config: &ConfigRequest,
+    ) -> Result<Self, InstanceError> {
         #[cfg(feature = "swappy")]
         {
             let _ = swappy_init();
@@ -76,50 +219,128 @@ impl EglInstance {
 
         let egl = &EGL_FUNCTIONS.0;
 
-        unsafe {
-            let egl_display = egl.GetDisplay(egl::DEFAULT_DISPLAY);
-            assert_ne!(egl_display, egl::NO_DISPLAY, "无法获取默认显示");
+        // 初始化 EGL
+        let (mut major_version, mut minor_version) = (0, 0);
+        let result = egl.Initialize(egl_display, &mut major_version, &mut minor_version);
+        if result == egl::FALSE {
+            return Err(egl_error("eglInitialize"));
+        }
 
-            // 初始化 EGL
-            let (mut major_version, mut minor_version) = (0, 0);
-            let result = egl.Initialize(egl_display, &mut major_version, &mut minor_version);
-            assert_ne!(result, egl::FALSE, "EGL 初始化失败");
+        let config = match egl_config_from_display(egl_display, config) {
+            Ok(config) => config,
+            Err(err) => {
+                egl.Terminate(egl_display);
+                return Err(err);
+            }
+        };
 
-            Ok(EglInstance {
-                display: egl_display,
-                context: None,
-                is_vsync,
-                #[cfg(feature = "fps")]
-                fps: AtomicU32::new(0),
-                #[cfg(feature = "fps")]
-                time: pi_share::ShareCell::new(std::time::Instant::now()),
-            })
+        Ok(EglInstance {
+            display: egl_display,
+            version: (major_version, minor_version),
+            config,
+            display_handle: None,
+            platform: None,
+            xlib_display: None,
+            is_vsync,
+            #[cfg(feature = "swappy")]
+            swappy_interval_ns: AtomicU64::new(0),
+        })
+    }
+
+    /// 列出显示连接上的所有配置。
+    pub fn configs(&self) -> Result<Vec<ConfigInfo>, InstanceError> {
+        unsafe {
+            let configs = all_configs(self.display)?;
+            Ok(configs
+                .into_iter()
+                .map(|config| config_info(self.display, config))
+                .collect())
         }
     }
 
+    /// 创建时选定的配置。
+    pub fn config(&self) -> ConfigInfo {
+        unsafe { config_info(self.display, self.config) }
+    }
+
     /// 创建带双缓冲的 EGL 表面（Surface）。
     ///
     /// # 参数
-    /// - `window`: 窗口句柄，必须实现 `HasRawWindowHandle` 和 `HasRawDisplayHandle`。
+    /// - `window`: 窗口句柄，必须实现 `HasWindowHandle` 和 `HasDisplayHandle`。
+    ///
+    /// Android 下表面持有 ANativeWindow 的引用；其他窗口系统的窗口必须比表面活得久。
+    ///
+    /// Wayland 窗口的表面大小由应用决定，必须用 `create_surface_with_size` 创建；
+    /// 实例应由窗口的显示句柄创建（`from_display_handle`）。
     ///
     /// # 返回值
     /// - `Result<EglSurface, InstanceError>`: 创建成功则返回 `EglSurface` 实例，失败则返回错误信息。
-    #[allow(deprecated)]
-    pub fn create_surface<W: HasRawWindowHandle + HasRawDisplayHandle>(
+    /// - 窗口不属于实例的显示连接时返回 `InstanceError::DisplayMismatch`：实例由显示句柄创建而窗口属于其他连接，
+    ///   或实例在无窗口系统的平台（surfaceless / 设备平台）上、在另一个窗口系统的平台上。
+    /// - Wayland 窗口返回 `InstanceError::SizeRequired`。
+    pub fn create_surface<W: HasWindowHandle + HasDisplayHandle>(
+        &self,
+        window: &W,
+    ) -> Result<EglSurface, InstanceError> {
+        let handle = window
+            .window_handle()
+            .map_err(|_| InstanceError::IncompatibleWindowHandle)?;
+        if let RawWindowHandle::Wayland(_) = handle.as_raw() {
+            return Err(InstanceError::SizeRequired);
+        }
+        // 其他窗口系统的表面跟随窗口大小，不使用这里的大小
+        self.create_surface_with_size(window, 0, 0)
+    }
+
+    /// 创建窗口表面，Wayland 窗口的初始大小为 `width` x `height`。
+    ///
+    /// 其他窗口系统的表面跟随窗口大小，忽略 `width` 和 `height`；其余同 `create_surface`。
+    pub fn create_surface_with_size<W: HasWindowHandle + HasDisplayHandle>(
         &self,
         window: &W,
+        width: u32,
+        height: u32,
     ) -> Result<EglSurface, InstanceError> {
         let egl = &EGL_FUNCTIONS.0;
         let egl_display = self.display;
 
+        // 窗口必须属于实例的显示连接
+        if self.display_handle.is_some() || self.platform.is_some() {
+            let handle = window
+                .display_handle()
+                .map_err(|_| InstanceError::IncompatibleWindowHandle)?
+                .as_raw();
+            let matches = match (&self.display_handle, self.platform) {
+                (Some(expected), _) => same_display(expected, &handle),
+                (None, Some(platform)) => platform_accepts(platform, &handle),
+                (None, None) => true,
+            };
+            if !matches {
+                return Err(InstanceError::DisplayMismatch);
+            }
+        }
+
         // 获取本地窗口句柄
-        let handle = window.raw_window_handle();
-        let native_window = if let Ok(RawWindowHandle::AndroidNdk(handle)) = handle {
-            handle.a_native_window.as_ptr()
-        } else if let Ok(RawWindowHandle::Xlib(XlibWindowHandle{window, .. })) = handle {
-            window as *mut c_void
-        } else {
-            return Err(InstanceError::IncompatibleWindowHandle);
+        let handle = window
+            .window_handle()
+            .map_err(|_| InstanceError::IncompatibleWindowHandle)?
+            .as_raw();
+        let mut wl_egl_window = None;
+        let mut native_window_ref = None;
+        let native_window = match handle {
+            RawWindowHandle::AndroidNdk(handle) => {
+                let window = NativeWindowRef::acquire(handle.a_native_window.as_ptr());
+                native_window_ref.insert(window).as_ptr()
+            }
+            RawWindowHandle::Xlib(XlibWindowHandle { window, .. }) => window as *mut c_void,
+            // xcb_window_t 和 Xlib 的 Window 都是 XID，两种平台上都直接传窗口 ID
+            RawWindowHandle::Xcb(XcbWindowHandle { window, .. }) => window.get() as *mut c_void,
+            RawWindowHandle::Wayland(handle) => {
+                let (width, height) = (width.max(1) as i32, height.max(1) as i32);
+                let window = WlEglWindow::new(handle.surface.as_ptr(), width, height)?;
+                wl_egl_window.insert(window).as_ptr()
+            }
+            _ => return Err(InstanceError::IncompatibleWindowHandle),
         };
         unsafe {
             #[cfg(feature = "swappy")]
@@ -127,10 +348,12 @@ impl EglInstance {
                 SwappyGL_setWindow(native_window);
                 let enable = SwappyGL_isEnabled();
                 println!("SwappyGL 启用状态: {}", enable);
+                // 设置窗口之后才能取到刷新周期
+                self.apply_swappy_interval();
             }
 
-            // 获取适合的 EGL 配置
-            let egl_config = egl_config_from_display(egl_display);
+            // 使用实例选定的 EGL 配置
+            let egl_config = self.config;
 
             // 创建窗口表面
             let attributes = [egl::NONE as EGLint];
@@ -140,73 +363,203 @@ impl EglInstance {
                 native_window,
                 attributes.as_ptr(),
             );
-            if egl_surface == egl::NO_SURFACE {
-                // 检查是否有缓存的 surface指针
+            let reused = egl_surface == egl::NO_SURFACE;
+            if reused {
+                // 先取错误码，缓存也没有时返回它
+                let err = egl_error("eglCreateWindowSurface");
+                // 检查是否有缓存的 surface指针，Wayland 表面不使用缓存
                 let ptr = *SURFACE_PTR.read().unwrap();
-                if ptr != 0 {
-                    egl_surface =  ptr as *mut c_void;
+                if ptr == 0 || wl_egl_window.is_some() {
+                    return Err(err);
                 }
-            } else {
+                egl_surface = ptr as *mut c_void;
+            } else if wl_egl_window.is_none() {
                 // 缓存创建的 surface 指针
                 *SURFACE_PTR.write().unwrap() = egl_surface as u64;
             }
 
-            assert_ne!(egl_surface, egl::NO_SURFACE, "创建窗口表面失败");
             // 获取表面尺寸
             let mut width = 0;
             let mut height = 0;
-            egl.QuerySurface(egl_display, egl_surface, egl::WIDTH as EGLint, &mut width);
-            egl.QuerySurface(egl_display, egl_surface, egl::HEIGHT as EGLint, &mut height);
-            assert_ne!(width, 0, "表面宽度为0");
-            assert_ne!(height, 0, "表面高度为0");
+            if egl.QuerySurface(egl_display, egl_surface, egl::WIDTH as EGLint, &mut width)
+                == egl::FALSE
+                || egl.QuerySurface(egl_display, egl_surface, egl::HEIGHT as EGLint, &mut height)
+                    == egl::FALSE
+            {
+                let err = egl_error("eglQuerySurface");
+                // 新建的表面还没有交给 EglSurface，按 EglSurface::drop 的规则处理：
+                // 只释放 Wayland 表面（安卓某些设备释放窗口表面会崩溃），其他表面不释放，只清掉缓存；
+                // 复用的缓存表面不处理
+                if !reused {
+                    if wl_egl_window.is_some() {
+                        egl.DestroySurface(egl_display, egl_surface);
+                    } else {
+                        let mut cached = SURFACE_PTR.write().unwrap();
+                        if *cached == egl_surface as u64 {
+                            *cached = 0;
+                        }
+                    }
+                }
+                return Err(err);
+            }
             Ok(EglSurface {
                 width,
                 height,
                 egl_surface,
                 egl_display,
+                is_pbuffer: false,
+                present_interval: AtomicI32::new(self.initial_swap_interval()),
+                swap_interval: AtomicI32::new(SWAP_INTERVAL_UNSET),
+                wl_egl_window,
+                native_window: native_window_ref,
             })
         }
     }
 
+    /// 创建离屏 pbuffer 表面。
+    ///
+    /// 实例选定的配置需要支持 pbuffer（`SurfaceTypes::PBUFFER`），否则返回 `InstanceError::PbufferUnsupported`。
+    ///
+    /// # 参数
+    /// - `width`, `height`: 表面尺寸。
+    /// - `format`: 绑定为纹理时的格式。
+    pub fn create_pbuffer_surface(
+        &self,
+        width: u32,
+        height: u32,
+        format: PbufferFormat,
+    ) -> Result<EglSurface, InstanceError> {
+        let egl = &EGL_FUNCTIONS.0;
+        let egl_display = self.display;
+
+        // 配置不支持时 eglCreatePbufferSurface 只返回 EGL_BAD_MATCH，这里给出明确的错误
+        let surface_type =
+            unsafe { get_config_attrib(egl_display, self.config, egl::SURFACE_TYPE as EGLint) };
+        if surface_type & egl::PBUFFER_BIT as EGLint == 0 {
+            return Err(InstanceError::PbufferUnsupported);
+        }
+
+        let mut attributes = vec![
+            egl::WIDTH as EGLint,
+            width as EGLint,
+            egl::HEIGHT as EGLint,
+            height as EGLint,
+        ];
+        let texture_format = match format {
+            PbufferFormat::NoTexture => None,
+            PbufferFormat::Rgb => Some(egl::TEXTURE_RGB),
+            PbufferFormat::Rgba => Some(egl::TEXTURE_RGBA),
+        };
+        if let Some(texture_format) = texture_format {
+            attributes.extend_from_slice(&[
+                egl::TEXTURE_FORMAT as EGLint,
+                texture_format as EGLint,
+                egl::TEXTURE_TARGET as EGLint,
+                egl::TEXTURE_2D as EGLint,
+            ]);
+        }
+        attributes.push(egl::NONE as EGLint);
+
+        let egl_surface =
+            unsafe { egl.CreatePbufferSurface(egl_display, self.config, attributes.as_ptr()) };
+        if egl_surface == egl::NO_SURFACE {
+            return Err(egl_error("eglCreatePbufferSurface"));
+        }
+
+        Ok(EglSurface {
+            width: width as i32,
+            height: height as i32,
+            egl_surface,
+            egl_display,
+            is_pbuffer: true,
+            present_interval: AtomicI32::new(self.initial_swap_interval()),
+            swap_interval: AtomicI32::new(SWAP_INTERVAL_UNSET),
+            wl_egl_window: None,
+            native_window: None,
+        })
+    }
+
     /// 创建 OpenGL ES 上下文。
     ///
+    /// # 参数
+    /// - `share`: 共享组中的另一个上下文，为 `None` 时创建新的共享组。
+    /// - `options`: 请求调试上下文时，EGL 1.5 下使用 EGL_CONTEXT_OPENGL_DEBUG，
+    ///   EGL 1.4 下使用 EGL_KHR_create_context；都不支持或驱动拒绝时退回普通上下文并打印警告。
+    ///
     /// # 返回值
     /// - `Result<EglContext, InstanceError>`: 创建成功则返回 `EglContext` 实例，失败则返回错误信息。
     #[allow(non_snake_case)]
-    pub fn create_context(&self) -> Result<EglContext, InstanceError> {
+    pub fn create_context(
+        &self,
+        share: Option<&EglContext>,
+        options: &ContextOptions,
+    ) -> Result<EglContext, InstanceError> {
         let egl = &EGL_FUNCTIONS.0;
         let egl_display = self.display;
+        let share_context = share.map_or(egl::NO_CONTEXT, |share| share.egl_context);
 
         unsafe {
             // 绑定 OpenGL ES API
             egl.BindAPI(egl::OPENGL_ES_API);
 
-            // 获取适合的 EGL 配置
-            let egl_config = egl_config_from_display(egl_display);
+            // 使用实例选定的 EGL 配置
+            let egl_config = self.config;
 
             // 上下文属性
-            let egl_context_attributes = [
+            let mut egl_context_attributes = vec![
                 egl::CONTEXT_CLIENT_VERSION as EGLint,
                 3, // 请求 OpenGL ES 3.0
-                egl::NONE as EGLint,
             ];
+            let base_len = egl_context_attributes.len();
+            if options.debug.is_some() {
+                if self.version >= (1, 5) {
+                    egl_context_attributes.extend_from_slice(&[
+                        egl::CONTEXT_OPENGL_DEBUG as EGLint,
+                        egl::TRUE as EGLint,
+                    ]);
+                } else if has_display_extension(egl_display, "EGL_KHR_create_context") {
+                    egl_context_attributes
+                        .extend_from_slice(&[CONTEXT_FLAGS_KHR, CONTEXT_OPENGL_DEBUG_BIT_KHR]);
+                } else {
+                    log::warn!(
+                        "EGL_KHR_create_context is unavailable, creating a non-debug context"
+                    );
+                }
+            }
+            egl_context_attributes.push(egl::NONE as EGLint);
 
             // 创建上下文
-            let egl_context = egl.CreateContext(
+            let mut egl_context = egl.CreateContext(
                 egl_display,
                 egl_config,
-                std::ptr::null_mut(),
+                share_context,
                 egl_context_attributes.as_ptr(),
             );
+            if egl_context == egl::NO_CONTEXT && egl_context_attributes.len() > base_len + 1 {
+                // 有的驱动不接受调试标志，去掉后重试
+                log::warn!(
+                    "{}, creating a non-debug context",
+                    egl_error("eglCreateContext")
+                );
+                egl_context_attributes.truncate(base_len);
+                egl_context_attributes.push(egl::NONE as EGLint);
+                egl_context = egl.CreateContext(
+                    egl_display,
+                    egl_config,
+                    share_context,
+                    egl_context_attributes.as_ptr(),
+                );
+            }
 
             if egl_context == egl::NO_CONTEXT {
-                let _err = egl.GetError();
-                return Err(InstanceError::ContextCreationFailed);
-        }
+                return Err(egl_error("eglCreateContext"));
+            }
 
             Ok(EglContext {
                 egl_context,
                 egl_display,
+                gl: OnceLock::new(),
+                debug: options.debug.clone(),
             })
         }
     }
@@ -217,115 +570,267 @@ impl EglInstance {
     /// # 参数
     /// - `surface`: 可选的表面，如果为 `None`，则解除绑定。
     /// - `context`: 可选的上下文，如果为 `None`，则清除当前上下文。
-    pub fn make_current(&mut self, surface: Option<&EglSurface>, context: Option<&EglContext>) {
+    ///
+    /// 当前线程已经是同样的绑定时直接返回，不调用 eglMakeCurrent。
+    pub fn make_current(
+        &mut self,
+        surface: Option<&EglSurface>,
+        context: Option<&EglContext>,
+    ) -> Result<(), InstanceError> {
         let egl = &EGL_FUNCTIONS.0;
         let egl_display = self.display;
 
-        if let Some(context) = context {
-            if let Some(surface) = surface {
-                // 绑定到目标表面和上下文
-                let ok = unsafe {
-                    egl.MakeCurrent(
-                        egl_display,
-                        surface.egl_surface,
-                        surface.egl_surface,
-                        context.egl_context,
-                    )
-                };
-                assert_ne!(ok, egl::FALSE, "绑定上下文失败");
-
-                // 禁用 VSync（如果需要）
-                if !self.is_vsync {
-                    let ok = unsafe { egl.SwapInterval(egl_display, 0) };
-                    if ok != egl::TRUE {
-                        println!("禁用 VSync 失败。错误码: {}", ok);
-                    }
-                }
-            } else {
-                // 只绑定到上下文，而不绑定到表面
-                let ok = unsafe {
-                    egl.MakeCurrent(
-                        egl_display,
-                        egl::NO_SURFACE,
-                        egl::NO_SURFACE,
-                        context.egl_context,
-                    )
-                };
-                assert_ne!(ok, egl::FALSE, "绑定上下文失败");
-            }
+        let binding = match (surface, context) {
+            // 绑定到目标表面和上下文
+            (Some(surface), Some(context)) => EglBinding {
+                display: egl_display,
+                draw: surface.egl_surface,
+                read: surface.egl_surface,
+                context: context.egl_context,
+            },
+            // 只绑定到上下文，而不绑定到表面
+            (None, Some(context)) => EglBinding {
+                display: egl_display,
+                context: context.egl_context,
+                ..EglBinding::NONE
+            },
+            // 清除当前上下文
+            (_, None) => EglBinding::NONE,
+        };
+        if binding == current() {
+            return Ok(());
+        }
+
+        let ok = unsafe {
+            egl.MakeCurrent(
+                egl_display,
+                binding.draw,
+                binding.read,
+                binding.context,
+            )
+        };
+        if ok == egl::FALSE {
+            return Err(egl_error("eglMakeCurrent"));
+        }
+        set_current(binding);
 
-            // 初始化 Glow 上下文
-            if self.context.is_none() {
-                let context = unsafe {
-                    glow::Context::from_loader_function(|symbol_name| get_gl_address(symbol_name))
-                };
-                let _ = self.context.replace(context);
+        // 应用表面的呈现模式，失败不影响绑定
+        if let Some(surface) = surface {
+            if let Err(err) = self.apply_swap_interval(surface) {
+                log::warn!("设置交换间隔失败: {}", err);
             }
+        }
+        Ok(())
+    }
+
+    /// 新建表面的交换间隔：关闭垂直同步时为 0，否则使用驱动的默认值
+    fn initial_swap_interval(&self) -> i32 {
+        if self.is_vsync {
+            SWAP_INTERVAL_UNSET
         } else {
-            // 清除当前上下文
-            unsafe {
-                let ok = egl.MakeCurrent(
-                    egl_display,
-                    egl::NO_SURFACE,
-                    egl::NO_SURFACE,
-                    egl::NO_CONTEXT,
-                );
-                assert_ne!(ok, egl::FALSE, "清除当前上下文失败");
-            }
+            0
         }
     }
 
-    /// 获取 Glow OpenGL 上下文。
+    /// 对当前绑定的 `surface` 设置要求的交换间隔；交换间隔是表面的属性，已经设置过时跳过
+    fn apply_swap_interval(&self, surface: &EglSurface) -> Result<(), InstanceError> {
+        let interval = surface.present_interval.load(Ordering::Relaxed);
+        let applied = surface.swap_interval.load(Ordering::Relaxed);
+        if interval == SWAP_INTERVAL_UNSET || interval == applied {
+            return Ok(());
+        }
+
+        let egl = &EGL_FUNCTIONS.0;
+        if unsafe { egl.SwapInterval(self.display, interval) } != egl::TRUE {
+            return Err(egl_error("eglSwapInterval"));
+        }
+        surface.swap_interval.store(interval, Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// 设置表面的呈现模式。
+    ///
+    /// 交换间隔限制在配置的 `EGL_MIN_SWAP_INTERVAL` 和 `EGL_MAX_SWAP_INTERVAL` 之间，
+    /// 不支持负的交换间隔时 `AdaptiveFifo` 退回 `Fifo`。
+    /// 表面正绑定在当前线程时立即生效，否则在下一次绑定时生效。
     ///
     /// # 返回值
-    /// - `&glow::Context`: Returns the OpenGL context.
-    #[inline]
-    pub fn get_glow<'a>(&'a self) -> &glow::Context {
-        self.context.as_ref().unwrap()
+    /// - 实际生效的呈现模式。
+    pub fn set_present_mode(
+        &self,
+        surface: &EglSurface,
+        mode: PresentMode,
+    ) -> Result<PresentMode, InstanceError> {
+        let (min, max) = unsafe {
+            (
+                get_config_attrib(self.display, self.config, egl::MIN_SWAP_INTERVAL as EGLint),
+                get_config_attrib(self.display, self.config, egl::MAX_SWAP_INTERVAL as EGLint),
+            )
+        };
+
+        let mut interval = mode.swap_interval();
+        if interval < min && mode == PresentMode::AdaptiveFifo {
+            interval = PresentMode::Fifo.swap_interval();
+        }
+        let interval = interval.clamp(min, max.max(min));
+
+        surface.present_interval.store(interval, Ordering::Relaxed);
+        if current().draw == surface.egl_surface {
+            self.apply_swap_interval(surface)?;
+        }
+        Ok(PresentMode::from_swap_interval(interval))
     }
 
-    /// 交换双缓冲区的内容，将后缓冲区显示到屏幕。
-    /// 在 WASM 环境中为空实现。
+    /// 在当前上下文的命令流中插入栅栏（eglCreateSync / EGL_SYNC_FENCE）。
+    ///
+    /// 当前线程上必须绑定着这个实例的上下文。
+    pub fn insert_fence(&self) -> Result<EglFence, InstanceError> {
+        unsafe { EglFence::new(self.display, self.version >= (1, 5)) }
+    }
+
+    /// 本地栅栏（EGL_ANDROID_native_fence_sync）。
     ///
     /// # 参数
-    /// - `surface`: 需要交换缓冲区的表面。
-    pub fn swap_buffers(&self, surface: &EglSurface) {
-        let egl_display = self.display;
+    /// - `fd`: 为 `None` 时在当前上下文的命令流中插入栅栏；否则导入这个 sync_file。
+    pub fn native_fence(&self, fd: Option<OwnedFd>) -> Result<EglFence, InstanceError> {
+        unsafe { EglFence::native(self.display, self.version >= (1, 5), fd) }
+    }
+
+    /// 由 `context` 中的 2D 纹理创建 EGLImage（EGL_GL_TEXTURE_2D）。
+    ///
+    /// # 参数
+    /// - `texture`: 纹理名，`level` 层必须已经定义。
+    pub fn create_image_from_texture(
+        &self,
+        context: &EglContext,
+        texture: u32,
+        level: i32,
+    ) -> Result<EglImage, InstanceError> {
+        unsafe {
+            EglImage::from_texture(
+                self.display,
+                self.version >= (1, 5),
+                context.egl_context,
+                texture,
+                level,
+            )
+        }
+    }
+
+    /// 由 `context` 中的渲染缓冲创建 EGLImage（EGL_GL_RENDERBUFFER）。
+    pub fn create_image_from_renderbuffer(
+        &self,
+        context: &EglContext,
+        renderbuffer: u32,
+    ) -> Result<EglImage, InstanceError> {
+        unsafe {
+            EglImage::from_renderbuffer(
+                self.display,
+                self.version >= (1, 5),
+                context.egl_context,
+                renderbuffer,
+            )
+        }
+    }
+
+    /// 导入 DMA-BUF，不需要绑定上下文。
+    pub fn import_dmabuf(&self, desc: &DmabufDesc) -> Result<EglImage, InstanceError> {
+        unsafe { EglImage::from_dmabuf(self.display, self.version >= (1, 5), desc) }
+    }
+
+    /// 可以导入的 DMA-BUF 格式（DRM fourcc）。
+    pub fn dmabuf_formats(&self) -> Result<Vec<u32>, InstanceError> {
+        unsafe { dmabuf_formats(self.display) }
+    }
+
+    /// 格式 `fourcc` 可以导入的修饰符。
+    pub fn dmabuf_modifiers(&self, fourcc: u32) -> Result<Vec<DmabufModifier>, InstanceError> {
+        unsafe { dmabuf_modifiers(self.display, fourcc) }
+    }
+
+    /// 当前线程上是否正绑定着 `surface` 和 `context`（`None` 表示没有绑定表面或上下文）。
+    pub fn is_current(&self, surface: Option<&EglSurface>, context: Option<&EglContext>) -> bool {
+        let binding = current();
+        let surface = surface.map_or(egl::NO_SURFACE, |surface| surface.egl_surface);
+        let context = context.map_or(egl::NO_CONTEXT, |context| context.egl_context);
+        let display = if context == egl::NO_CONTEXT { egl::NO_DISPLAY } else { self.display };
+        binding
+            == EglBinding {
+                display,
+                draw: surface,
+                read: surface,
+                context,
+            }
+    }
+
+    /// 由后端实现帧率限制。
+    ///
+    /// # 返回值
+    /// - 启用 `swappy` 特性并且 Swappy 可用时设置它的最小交换间隔并返回 true；
+    ///   否则返回 false，由软件等待实现。
+    pub fn set_frame_pacing(&self, pacing: &FramePacing) -> bool {
         #[cfg(feature = "swappy")]
         {
-            unsafe { SwappyGL_swap(egl_display, surface.egl_surface) };
+            // 0 表示不限制，使用屏幕的刷新周期
+            let swap_ns = pacing.frame_time().map_or(0, |frame_time| frame_time.as_nanos() as u64);
+            self.swappy_interval_ns.store(swap_ns, Ordering::Relaxed);
+            unsafe {
+                if SwappyGL_isEnabled() == 0 {
+                    return false;
+                }
+                self.apply_swappy_interval();
+            }
+            true
         }
         #[cfg(not(feature = "swappy"))]
         {
-            let egl = &EGL_FUNCTIONS.0;
-            unsafe { egl.SwapBuffers(egl_display, surface.egl_surface) };
+            let _ = pacing;
+            false
         }
+    }
 
-        #[cfg(feature = "fps")]
-        {
-            // 增加帧率计数器
-            self.fps.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
-
-            // 检查是否需要更新 FPS 显示
-            let mut time = self.time.borrow_mut();
-            if time.elapsed().as_millis() > 1000 {
-                println!("PI_EGL FPS: {:?}", self.fps);
-                self.fps.store(0, std::sync::atomic::Ordering::Relaxed);
-                *time = std::time::Instant::now();
-            }
+    /// 把 `set_frame_pacing` 设置的交换间隔交给 Swappy。
+    ///
+    /// 没有设置窗口时刷新周期为 0，这时不设置，等 `create_surface` 设置窗口后再调用。
+    #[cfg(feature = "swappy")]
+    unsafe fn apply_swappy_interval(&self) {
+        let swap_ns = match self.swappy_interval_ns.load(Ordering::Relaxed) {
+            0 => SwappyGL_getRefreshPeriodNanos(),
+            swap_ns => swap_ns,
+        };
+        if swap_ns != 0 {
+            SwappyGL_setSwapIntervalNS(swap_ns);
         }
-        
     }
-}
 
-fn get_gl_address(symbol_name: &str) -> *const c_void {
-    unsafe {
-        let egl = &EGL_FUNCTIONS.0;
-        let symbol_name: CString = CString::new(symbol_name).unwrap();
-        let v =
-            egl.GetProcAddress(symbol_name.as_ptr() as *const u8 as *const c_char) as *const c_void;
-        // println!("gl symbol_name {:?} ptr is {:?}!!", symbol_name, v);
-        v
+    /// 交换双缓冲区的内容，将后缓冲区显示到屏幕。
+    /// 在 WASM 环境中为空实现。
+    ///
+    /// # 参数
+    /// - `surface`: 需要交换缓冲区的表面。
+    ///
+    /// # 返回值
+    /// - 交换失败（如 `EGL_CONTEXT_LOST`）时返回错误。
+    pub fn swap_buffers(&self, surface: &EglSurface) -> Result<(), InstanceError> {
+        // pbuffer 没有前后缓冲，交换无效果
+        if surface.is_pbuffer {
+            return Ok(());
+        }
+
+        let egl_display = self.display;
+        #[cfg(feature = "swappy")]
+        let ok = unsafe { SwappyGL_swap(egl_display, surface.egl_surface) != 0 };
+        #[cfg(not(feature = "swappy"))]
+        let ok = {
+            let egl = &EGL_FUNCTIONS.0;
+            unsafe { egl.SwapBuffers(egl_display, surface.egl_surface) != egl::FALSE }
+        };
+
+        if ok {
+            Ok(())
+        } else {
+            Err(egl_error("eglSwapBuffers"))
+        }
     }
 }
 
@@ -360,7 +865,7 @@ fn swappy_init() -> Result<(), InstanceError> {
         SwappyGL_setAutoSwapInterval(0);
         SwappyGL_setAutoPipelineMode(0);
         SwappyGL_enableStats(0);
-        SwappyGL_setSwapIntervalNS(1000000000 / 120);
+        // 交换间隔在 create_surface 设置窗口后由 apply_swappy_interval 设置，这时刷新周期可能还是 0
     }
     vm.detach_current_thread();
     Ok(())
@@ -1,7 +1,14 @@
 pub mod instance;
 pub mod surface;
 pub mod context;
+pub mod sync;
+pub mod image;
+mod adapter;
+mod current;
+mod display;
 mod util;
+mod wayland;
+mod xlib;
 
 #[cfg(feature = "swappy")]
 mod swappy;
@@ -1,22 +1,102 @@
+use std::os::raw::c_void;
+use std::sync::atomic::AtomicI32;
+
 use crate::platform::android::egl::types::{EGLDisplay, EGLSurface};
 
-use super::util::EGL_FUNCTIONS;
+use super::{
+    current::forget,
+    egl,
+    util::EGL_FUNCTIONS,
+    wayland::WlEglWindow,
+};
+
+/// 还没有对表面调用过 eglSwapInterval
+pub(crate) const SWAP_INTERVAL_UNSET: i32 = i32::MIN;
+
+#[cfg(target_os = "android")]
+#[link(name = "android")]
+extern "C" {
+    fn ANativeWindow_acquire(window: *mut c_void);
+    fn ANativeWindow_release(window: *mut c_void);
+}
 
+/// ANativeWindow 的引用计数，表面存在期间本地窗口不会被释放
 #[derive(Debug, Eq, PartialEq)]
+pub(crate) struct NativeWindowRef(*mut c_void);
+
+impl NativeWindowRef {
+    pub(crate) fn acquire(window: *mut c_void) -> Self {
+        #[cfg(target_os = "android")]
+        unsafe {
+            ANativeWindow_acquire(window)
+        };
+        Self(window)
+    }
+
+    #[inline]
+    pub(crate) fn as_ptr(&self) -> *mut c_void {
+        self.0
+    }
+}
+
+impl Drop for NativeWindowRef {
+    fn drop(&mut self) {
+        #[cfg(target_os = "android")]
+        unsafe {
+            ANativeWindow_release(self.0)
+        };
+    }
+}
+
+#[derive(Debug)]
 pub struct EglSurface {
+    /// 创建时查询到的大小
+    #[allow(dead_code)]
     pub(crate) width: i32,
+    #[allow(dead_code)]
     pub(crate) height: i32,
     pub(crate) egl_surface: EGLSurface,
     pub(crate) egl_display: EGLDisplay,
+    /// 离屏 pbuffer 表面，没有前后缓冲可交换
+    pub(crate) is_pbuffer: bool,
+    /// 要求的交换间隔（呈现模式），`SWAP_INTERVAL_UNSET` 表示使用驱动的默认值
+    pub(crate) present_interval: AtomicI32,
+    /// 最后一次对这个表面设置的交换间隔，`SWAP_INTERVAL_UNSET` 表示没有设置过
+    pub(crate) swap_interval: AtomicI32,
+    /// Wayland 窗口表面的本地窗口，在 EGL 表面之后释放
+    pub(crate) wl_egl_window: Option<WlEglWindow>,
+    /// Android 窗口表面持有的 ANativeWindow 引用，在 EGL 表面之后释放；只用于持有
+    #[allow(dead_code)]
+    pub(crate) native_window: Option<NativeWindowRef>,
+}
+
+impl EglSurface {
+    /// 窗口大小变化时调用，只有 Wayland 窗口需要；下一次交换缓冲时生效
+    pub(crate) fn resize(&self, width: u32, height: u32) {
+        if let Some(window) = &self.wl_egl_window {
+            window.resize(width as i32, height as i32);
+        }
+    }
 }
 
+impl PartialEq for EglSurface {
+    fn eq(&self, other: &Self) -> bool {
+        self.egl_surface == other.egl_surface && self.egl_display == other.egl_display
+    }
+}
+
+impl Eq for EglSurface {}
+
 unsafe impl Sync for EglSurface {}
 unsafe impl Send for EglSurface {}
 
 impl Drop for EglSurface {
     fn drop(&mut self) {
-        let _egl = &EGL_FUNCTIONS.0;
-        // todo： 安卓某些设备释放这个会导致崩溃
-        // unsafe { egl.DestroySurface(self.egl_display, self.egl_surface) };
+        forget(egl::NO_DISPLAY, self.egl_surface, egl::NO_CONTEXT);
+        let egl = &EGL_FUNCTIONS.0;
+        // todo： 安卓某些设备释放窗口表面会导致崩溃，只释放 pbuffer 和 Wayland 表面
+        if self.is_pbuffer || self.wl_egl_window.is_some() {
+            unsafe { egl.DestroySurface(self.egl_display, self.egl_surface) };
+        }
     }
 }
@@ -0,0 +1,222 @@
+use std::os::fd::{AsRawFd, FromRawFd, IntoRawFd, OwnedFd};
+use std::time::Duration;
+
+use super::{
+    display::has_display_extension,
+    egl::{
+        self,
+        types::{EGLAttrib, EGLDisplay, EGLSync, EGLenum},
+        EGLint,
+    },
+    util::{egl_error, EGL_FUNCTIONS},
+};
+use crate::InstanceError;
+
+/// EGL 栅栏同步对象（EGL_SYNC_FENCE 或 EGL_SYNC_NATIVE_FENCE_ANDROID）
+///
+/// EGL 1.5 下使用核心函数，EGL 1.4 下使用 EGL_KHR_fence_sync / EGL_KHR_wait_sync。
+#[derive(Debug)]
+pub struct EglFence {
+    pub(crate) egl_sync: EGLSync,
+    pub(crate) egl_display: EGLDisplay,
+    /// 由 eglCreateSyncKHR 创建，之后也只能用 KHR 函数
+    khr: bool,
+}
+
+unsafe impl Sync for EglFence {}
+unsafe impl Send for EglFence {}
+
+impl EglFence {
+    /// 在当前上下文的命令流中插入栅栏。
+    ///
+    /// # 参数
+    /// - `core`: 显示连接的版本不低于 EGL 1.5，使用核心函数。
+    ///
+    /// # 返回值
+    /// - 两种函数都不可用时返回 `InstanceError::RequiredExtensionUnavailable`。
+    /// - 当前线程没有绑定这个显示连接上的上下文时返回 `EGL_BAD_MATCH`。
+    pub(crate) unsafe fn new(egl_display: EGLDisplay, core: bool) -> Result<Self, InstanceError> {
+        let attributes = [egl::NONE as EGLAttrib];
+        Self::create(egl_display, core, egl::SYNC_FENCE, &attributes)
+    }
+
+    /// 本地栅栏（EGL_ANDROID_native_fence_sync）。
+    ///
+    /// # 参数
+    /// - `fd`: 为 `None` 时在当前上下文的命令流中插入栅栏，之后可以导出 sync_file；
+    ///   否则导入这个 sync_file，成功后由 EGL 持有并在销毁时关闭，失败时关闭。
+    ///
+    /// # 返回值
+    /// - 不支持 EGL_ANDROID_native_fence_sync 时返回 `InstanceError::RequiredExtensionUnavailable`。
+    pub(crate) unsafe fn native(
+        egl_display: EGLDisplay,
+        core: bool,
+        fd: Option<OwnedFd>,
+    ) -> Result<Self, InstanceError> {
+        if !has_display_extension(egl_display, "EGL_ANDROID_native_fence_sync") {
+            return Err(InstanceError::RequiredExtensionUnavailable);
+        }
+
+        let raw_fd = fd.as_ref().map_or(egl::NO_NATIVE_FENCE_FD_ANDROID, |fd| fd.as_raw_fd());
+        let attributes = [
+            egl::SYNC_NATIVE_FENCE_FD_ANDROID as EGLAttrib,
+            raw_fd as EGLAttrib,
+            egl::NONE as EGLAttrib,
+        ];
+        let fence = Self::create(egl_display, core, egl::SYNC_NATIVE_FENCE_ANDROID, &attributes)?;
+        // 创建成功后 fd 归 EGL 所有
+        if let Some(fd) = fd {
+            let _ = fd.into_raw_fd();
+        }
+        Ok(fence)
+    }
+
+    /// eglCreateSync，不能使用时退回 eglCreateSyncKHR；`attributes` 以 `EGL_NONE` 结尾
+    unsafe fn create(
+        egl_display: EGLDisplay,
+        core: bool,
+        sync_type: EGLenum,
+        attributes: &[EGLAttrib],
+    ) -> Result<Self, InstanceError> {
+        let egl = &EGL_FUNCTIONS.0;
+
+        if core && egl.CreateSync.is_loaded() {
+            let egl_sync = egl.CreateSync(egl_display, sync_type, attributes.as_ptr());
+            if egl_sync == egl::NO_SYNC {
+                return Err(egl_error("eglCreateSync"));
+            }
+            return Ok(Self {
+                egl_sync,
+                egl_display,
+                khr: false,
+            });
+        }
+
+        if !has_display_extension(egl_display, "EGL_KHR_fence_sync") || !egl.CreateSyncKHR.is_loaded()
+        {
+            return Err(InstanceError::RequiredExtensionUnavailable);
+        }
+        let attributes: Vec<EGLint> = attributes.iter().map(|v| *v as EGLint).collect();
+        let egl_sync = egl.CreateSyncKHR(egl_display, sync_type, attributes.as_ptr());
+        if egl_sync == egl::NO_SYNC {
+            return Err(egl_error("eglCreateSyncKHR"));
+        }
+        Ok(Self {
+            egl_sync,
+            egl_display,
+            khr: true,
+        })
+    }
+
+    /// 导出为 sync_file（eglDupNativeFenceFDANDROID），每次调用返回一个新的 fd。
+    ///
+    /// 只有本地栅栏可以导出；插入栅栏的上下文需要先刷新命令，否则返回 `EGL_BAD_PARAMETER`。
+    pub fn export_fd(&self) -> Result<OwnedFd, InstanceError> {
+        let egl = &EGL_FUNCTIONS.0;
+        if !egl.DupNativeFenceFDANDROID.is_loaded() {
+            return Err(InstanceError::RequiredExtensionUnavailable);
+        }
+
+        let fd = unsafe { egl.DupNativeFenceFDANDROID(self.egl_display, self.egl_sync) };
+        if fd == egl::NO_NATIVE_FENCE_FD_ANDROID {
+            return Err(egl_error("eglDupNativeFenceFDANDROID"));
+        }
+        Ok(unsafe { OwnedFd::from_raw_fd(fd) })
+    }
+
+    /// 在 CPU 上等待栅栏，等待前刷新栅栏所在上下文的命令（EGL_SYNC_FLUSH_COMMANDS_BIT）。
+    ///
+    /// # 参数
+    /// - `timeout`: 最长等待时间，`None` 表示一直等待。
+    ///
+    /// # 返回值
+    /// - 栅栏已触发返回 true，超时返回 false。
+    pub fn client_wait(&self, timeout: Option<Duration>) -> Result<bool, InstanceError> {
+        let egl = &EGL_FUNCTIONS.0;
+        let timeout = timeout.map_or(egl::FOREVER, |timeout| {
+            timeout.as_nanos().min(egl::FOREVER as u128 - 1) as u64
+        });
+        let flags = egl::SYNC_FLUSH_COMMANDS_BIT as EGLint;
+
+        let (result, op) = unsafe {
+            if self.khr {
+                let result = egl.ClientWaitSyncKHR(self.egl_display, self.egl_sync, flags, timeout);
+                (result, "eglClientWaitSyncKHR")
+            } else {
+                let result = egl.ClientWaitSync(self.egl_display, self.egl_sync, flags, timeout);
+                (result, "eglClientWaitSync")
+            }
+        };
+        match result as u32 {
+            egl::CONDITION_SATISFIED => Ok(true),
+            egl::TIMEOUT_EXPIRED => Ok(false),
+            _ => Err(egl_error(op)),
+        }
+    }
+
+    /// 让当前上下文在 GPU 上等待栅栏，立即返回，不阻塞 CPU。
+    ///
+    /// # 返回值
+    /// - EGL 1.4 下不支持 EGL_KHR_wait_sync 时返回 `InstanceError::RequiredExtensionUnavailable`。
+    pub fn server_wait(&self) -> Result<(), InstanceError> {
+        let egl = &EGL_FUNCTIONS.0;
+        unsafe {
+            if !self.khr {
+                if egl.WaitSync(self.egl_display, self.egl_sync, 0) == egl::FALSE {
+                    return Err(egl_error("eglWaitSync"));
+                }
+                return Ok(());
+            }
+
+            if !has_display_extension(self.egl_display, "EGL_KHR_wait_sync")
+                || !egl.WaitSyncKHR.is_loaded()
+            {
+                return Err(InstanceError::RequiredExtensionUnavailable);
+            }
+            if egl.WaitSyncKHR(self.egl_display, self.egl_sync, 0) == egl::FALSE as EGLint {
+                return Err(egl_error("eglWaitSyncKHR"));
+            }
+        }
+        Ok(())
+    }
+
+    /// 查询栅栏是否已触发，不等待、不刷新命令。
+    pub fn is_signaled(&self) -> Result<bool, InstanceError> {
+        let egl = &EGL_FUNCTIONS.0;
+        let status = unsafe {
+            if self.khr {
+                let mut value: EGLint = 0;
+                let attribute = egl::SYNC_STATUS as EGLint;
+                if egl.GetSyncAttribKHR(self.egl_display, self.egl_sync, attribute, &mut value)
+                    == egl::FALSE
+                {
+                    return Err(egl_error("eglGetSyncAttribKHR"));
+                }
+                value as u32
+            } else {
+                let mut value: EGLAttrib = 0;
+                let attribute = egl::SYNC_STATUS as EGLint;
+                if egl.GetSyncAttrib(self.egl_display, self.egl_sync, attribute, &mut value)
+                    == egl::FALSE
+                {
+                    return Err(egl_error("eglGetSyncAttrib"));
+                }
+                value as u32
+            }
+        };
+        Ok(status == egl::SIGNALED)
+    }
+}
+
+impl Drop for EglFence {
+    fn drop(&mut self) {
+        let egl = &EGL_FUNCTIONS.0;
+        unsafe {
+            if self.khr {
+                egl.DestroySyncKHR(self.egl_display, self.egl_sync);
+            } else {
+                egl.DestroySync(self.egl_display, self.egl_sync);
+            }
+        }
+    }
+}
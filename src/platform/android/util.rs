@@ -1,6 +1,10 @@
 use crate::platform::android::egl;
 use crate::platform::android::egl::types::{EGLConfig, EGLDisplay, EGLint};
 use crate::platform::android::egl::Egl;
+use crate::{
+    ConfigCaveat, ConfigInfo, ConfigRequest, InstanceError, RenderableTypes, SurfaceTypes,
+    Transparency,
+};
 use libc::{dlopen, dlsym, RTLD_LAZY};
 use std::{
     ffi::CString,
@@ -32,7 +36,19 @@ fn get_egl_address(symbol_name: &str) -> *const c_void {
     unsafe {
         let symbol_name: CString = CString::new(symbol_name).unwrap();
         let symbol_ptr = symbol_name.as_ptr() as *const u8 as *const c_char;
-        let v = dlsym(EGL_LIBRARY.0, symbol_ptr) as *const c_void;
+        let mut v = dlsym(EGL_LIBRARY.0, symbol_ptr) as *const c_void;
+        // 扩展函数一般不导出，只能通过 eglGetProcAddress 取得
+        if v.is_null() {
+            let get_proc_address = dlsym(
+                EGL_LIBRARY.0,
+                &b"eglGetProcAddress\0"[0] as *const u8 as *const c_char,
+            );
+            if !get_proc_address.is_null() {
+                let get_proc_address: extern "system" fn(*const c_char) -> *const c_void =
+                    std::mem::transmute(get_proc_address);
+                v = get_proc_address(symbol_ptr);
+            }
+        }
         // println!("egl {:?} symbol_name {:?} ptr is {:?}!!", EGL_LIBRARY.0, symbol_name, v);
         v
     }
@@ -43,38 +59,205 @@ fn get_egl_address(symbol_name: &str) -> *const c_void {
 //         .with(|egl| mem::transmute(egl.GetProcAddress(&name[0] as *const u8 as *const c_char)))
 // }
 
-pub(crate) unsafe fn egl_config_from_display(egl_display: EGLDisplay) -> EGLConfig {
-    let config_attributes = [
+/// 取 eglGetError 的值，构造 `op` 失败的错误
+pub(crate) fn egl_error(op: &'static str) -> InstanceError {
+    let egl = &EGL_FUNCTIONS.0;
+    let code = unsafe { egl.GetError() };
+    InstanceError::egl(op, code)
+}
+
+/// 读取配置的单个属性
+pub(crate) unsafe fn get_config_attrib(
+    egl_display: EGLDisplay,
+    config: EGLConfig,
+    attribute: EGLint,
+) -> EGLint {
+    let egl = &EGL_FUNCTIONS.0;
+    let mut value = 0;
+    egl.GetConfigAttrib(egl_display, config, attribute, &mut value);
+    value
+}
+
+/// 按 `request` 选择配置
+///
+/// 先用 eglChooseConfig 取得所有满足最小要求的配置，再按 `request.ranking` 排序取第一个。
+pub(crate) unsafe fn egl_config_from_display(
+    egl_display: EGLDisplay,
+    request: &ConfigRequest,
+) -> Result<EGLConfig, InstanceError> {
+    // 指定了配置 ID 时，EGL 会忽略其他属性
+    if let Some(id) = request.config_id {
+        let config_attributes = [egl::CONFIG_ID as EGLint, id, egl::NONE as EGLint];
+        let egl = &EGL_FUNCTIONS.0;
+        let (mut config, mut config_count) = (std::ptr::null(), 0);
+        let result = egl.ChooseConfig(
+            egl_display,
+            config_attributes.as_ptr(),
+            &mut config,
+            1,
+            &mut config_count,
+        );
+        if result == egl::FALSE {
+            return Err(egl_error("eglChooseConfig"));
+        }
+        if config_count == 0 {
+            return Err(InstanceError::egl("eglChooseConfig", egl::BAD_CONFIG as EGLint));
+        }
+        return Ok(config);
+    }
+
+    let mut config_attributes = vec![
         egl::RENDERABLE_TYPE as EGLint,
         egl::OPENGL_ES2_BIT as EGLint, // Request opengl ES2.0
         egl::SURFACE_TYPE as EGLint,
-        egl::WINDOW_BIT as EGLint,
+        request.surface_types.bits() as EGLint,
         egl::BLUE_SIZE as EGLint,
-        8,
+        request.blue_size as EGLint,
         egl::GREEN_SIZE as EGLint,
-        8,
+        request.green_size as EGLint,
         egl::RED_SIZE as EGLint,
-        8,
+        request.red_size as EGLint,
+        egl::ALPHA_SIZE as EGLint,
+        request.alpha_size as EGLint,
         egl::DEPTH_SIZE as EGLint,
-        24,
-        egl::NONE as EGLint,
-        0,
-        0,
-        0,
-    ]
-    .to_vec();
+        request.depth_size as EGLint,
+        egl::STENCIL_SIZE as EGLint,
+        request.stencil_size as EGLint,
+    ];
+    if request.samples > 0 {
+        config_attributes.extend_from_slice(&[
+            egl::SAMPLE_BUFFERS as EGLint,
+            1,
+            egl::SAMPLES as EGLint,
+            request.samples as EGLint,
+        ]);
+    }
+    config_attributes.push(egl::NONE as EGLint);
 
     let egl = &EGL_FUNCTIONS.0;
 
-    let (mut config, mut config_count) = (std::ptr::null(), 0);
+    // 先查询满足条件的配置数量
+    let mut config_count = 0;
     let result = egl.ChooseConfig(
         egl_display,
         config_attributes.as_ptr(),
-        &mut config,
-        1,
+        std::ptr::null_mut(),
+        0,
         &mut config_count,
     );
-    assert_ne!(result, egl::FALSE);
-    assert!(config_count > 0);
-    config
+    if result == egl::FALSE {
+        return Err(egl_error("eglChooseConfig"));
+    }
+    if config_count == 0 {
+        // 调用成功，但没有匹配的配置
+        return Err(InstanceError::egl("eglChooseConfig", egl::BAD_CONFIG as EGLint));
+    }
+
+    let mut configs = vec![std::ptr::null(); config_count as usize];
+    let result = egl.ChooseConfig(
+        egl_display,
+        config_attributes.as_ptr(),
+        configs.as_mut_ptr(),
+        config_count,
+        &mut config_count,
+    );
+    if result == egl::FALSE {
+        return Err(egl_error("eglChooseConfig"));
+    }
+    configs.truncate(config_count as usize);
+
+    // 稳定排序：排序键相同的配置保持驱动给出的顺序
+    let ranking = request.ranking;
+    configs.sort_by_cached_key(|config| {
+        let attrib = |attribute| get_config_attrib(egl_display, *config, attribute as EGLint);
+
+        let slow = ranking.avoid_slow && attrib(egl::CONFIG_CAVEAT) == egl::SLOW_CONFIG as EGLint;
+        let inexact = ranking.prefer_exact
+            && (attrib(egl::RED_SIZE) != request.red_size as EGLint
+                || attrib(egl::GREEN_SIZE) != request.green_size as EGLint
+                || attrib(egl::BLUE_SIZE) != request.blue_size as EGLint
+                || attrib(egl::ALPHA_SIZE) != request.alpha_size as EGLint
+                || attrib(egl::DEPTH_SIZE) != request.depth_size as EGLint
+                || attrib(egl::STENCIL_SIZE) != request.stencil_size as EGLint
+                || attrib(egl::SAMPLES) != request.samples as EGLint);
+        let msaa = ranking.prefer_no_msaa && attrib(egl::SAMPLE_BUFFERS) > 0;
+
+        (slow, inexact, msaa)
+    });
+
+    Ok(configs[0])
+}
+
+/// 显示连接上的所有配置
+pub(crate) unsafe fn all_configs(egl_display: EGLDisplay) -> Result<Vec<EGLConfig>, InstanceError> {
+    let egl = &EGL_FUNCTIONS.0;
+
+    let mut config_count = 0;
+    if egl.GetConfigs(egl_display, std::ptr::null_mut(), 0, &mut config_count) == egl::FALSE {
+        return Err(egl_error("eglGetConfigs"));
+    }
+
+    let mut configs = vec![std::ptr::null(); config_count as usize];
+    if egl.GetConfigs(
+        egl_display,
+        configs.as_mut_ptr(),
+        config_count,
+        &mut config_count,
+    ) == egl::FALSE
+    {
+        return Err(egl_error("eglGetConfigs"));
+    }
+    configs.truncate(config_count as usize);
+    Ok(configs)
+}
+
+/// 读取配置的属性
+pub(crate) unsafe fn config_info(egl_display: EGLDisplay, config: EGLConfig) -> ConfigInfo {
+    let attrib = |attribute| get_config_attrib(egl_display, config, attribute as EGLint);
+
+    let caveat = match attrib(egl::CONFIG_CAVEAT) as u32 {
+        egl::SLOW_CONFIG => ConfigCaveat::Slow,
+        egl::NON_CONFORMANT_CONFIG => ConfigCaveat::NonConformant,
+        _ => ConfigCaveat::None,
+    };
+    let transparency = if attrib(egl::TRANSPARENT_TYPE) as u32 == egl::TRANSPARENT_RGB {
+        Transparency::Rgb {
+            red: attrib(egl::TRANSPARENT_RED_VALUE),
+            green: attrib(egl::TRANSPARENT_GREEN_VALUE),
+            blue: attrib(egl::TRANSPARENT_BLUE_VALUE),
+        }
+    } else {
+        Transparency::None
+    };
+
+    ConfigInfo {
+        id: attrib(egl::CONFIG_ID),
+        red_size: attrib(egl::RED_SIZE) as u8,
+        green_size: attrib(egl::GREEN_SIZE) as u8,
+        blue_size: attrib(egl::BLUE_SIZE) as u8,
+        alpha_size: attrib(egl::ALPHA_SIZE) as u8,
+        depth_size: attrib(egl::DEPTH_SIZE) as u8,
+        stencil_size: attrib(egl::STENCIL_SIZE) as u8,
+        buffer_size: attrib(egl::BUFFER_SIZE) as u8,
+        samples: attrib(egl::SAMPLES) as u8,
+        renderable_types: RenderableTypes::from_bits(attrib(egl::RENDERABLE_TYPE) as u32),
+        surface_types: SurfaceTypes::from_bits(attrib(egl::SURFACE_TYPE) as u32),
+        caveat,
+        native_visual_id: attrib(egl::NATIVE_VISUAL_ID),
+        transparency,
+        min_swap_interval: attrib(egl::MIN_SWAP_INTERVAL),
+        max_swap_interval: attrib(egl::MAX_SWAP_INTERVAL),
+    }
+}
+
+/// GL 函数地址，用于加载 glow 函数表
+pub(crate) fn get_gl_address(symbol_name: &str) -> *const c_void {
+    unsafe {
+        let egl = &EGL_FUNCTIONS.0;
+        let symbol_name: CString = CString::new(symbol_name).unwrap();
+        let v =
+            egl.GetProcAddress(symbol_name.as_ptr() as *const u8 as *const c_char) as *const c_void;
+        // println!("gl symbol_name {:?} ptr is {:?}!!", symbol_name, v);
+        v
+    }
 }
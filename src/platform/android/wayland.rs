@@ -0,0 +1,82 @@
+use std::os::raw::{c_int, c_void};
+
+use libc::{dlopen, dlsym, RTLD_LAZY};
+
+use crate::InstanceError;
+
+type CreateFn = unsafe extern "C" fn(surface: *mut c_void, width: c_int, height: c_int) -> *mut c_void;
+type DestroyFn = unsafe extern "C" fn(window: *mut c_void);
+type ResizeFn = unsafe extern "C" fn(window: *mut c_void, width: c_int, height: c_int, dx: c_int, dy: c_int);
+
+/// libwayland-egl 中用到的函数
+struct WaylandEglFunctions {
+    create: CreateFn,
+    destroy: DestroyFn,
+    resize: ResizeFn,
+}
+
+lazy_static! {
+    /// 运行时加载 libwayland-egl，非 Wayland 环境下可能不存在
+    static ref WAYLAND_EGL: Option<WaylandEglFunctions> = unsafe {
+        let library = dlopen(
+            &b"libwayland-egl.so.1\0"[0] as *const u8 as *const _,
+            RTLD_LAZY,
+        );
+        if library.is_null() {
+            return None;
+        }
+
+        let create = dlsym(library, &b"wl_egl_window_create\0"[0] as *const u8 as *const _);
+        let destroy = dlsym(library, &b"wl_egl_window_destroy\0"[0] as *const u8 as *const _);
+        let resize = dlsym(library, &b"wl_egl_window_resize\0"[0] as *const u8 as *const _);
+        if create.is_null() || destroy.is_null() || resize.is_null() {
+            return None;
+        }
+
+        Some(WaylandEglFunctions {
+            create: std::mem::transmute::<*mut c_void, CreateFn>(create),
+            destroy: std::mem::transmute::<*mut c_void, DestroyFn>(destroy),
+            resize: std::mem::transmute::<*mut c_void, ResizeFn>(resize),
+        })
+    };
+}
+
+/// wl_egl_window，Wayland 窗口表面的本地窗口
+#[derive(Debug, Eq, PartialEq)]
+pub(crate) struct WlEglWindow(*mut c_void);
+
+impl WlEglWindow {
+    /// 为 wl_surface 创建 wl_egl_window
+    pub(crate) fn new(surface: *mut c_void, width: i32, height: i32) -> Result<Self, InstanceError> {
+        let functions = match WAYLAND_EGL.as_ref() {
+            Some(functions) => functions,
+            None => return Err(InstanceError::LibraryLoadFailed("libwayland-egl.so.1")),
+        };
+
+        let window = unsafe { (functions.create)(surface, width, height) };
+        if window.is_null() {
+            return Err(InstanceError::IncompatibleWindowHandle);
+        }
+        Ok(Self(window))
+    }
+
+    #[inline]
+    pub(crate) fn as_ptr(&self) -> *mut c_void {
+        self.0
+    }
+
+    /// 下一次 eglSwapBuffers 时生效
+    pub(crate) fn resize(&self, width: i32, height: i32) {
+        if let Some(functions) = WAYLAND_EGL.as_ref() {
+            unsafe { (functions.resize)(self.0, width, height, 0, 0) };
+        }
+    }
+}
+
+impl Drop for WlEglWindow {
+    fn drop(&mut self) {
+        if let Some(functions) = WAYLAND_EGL.as_ref() {
+            unsafe { (functions.destroy)(self.0) };
+        }
+    }
+}
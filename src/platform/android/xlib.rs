@@ -0,0 +1,133 @@
+use std::os::raw::{c_char, c_int, c_void};
+
+use libc::{dlopen, dlsym, RTLD_LAZY};
+
+use crate::InstanceError;
+
+type OpenDisplayFn = unsafe extern "C" fn(name: *const c_char) -> *mut c_void;
+type CloseDisplayFn = unsafe extern "C" fn(display: *mut c_void) -> c_int;
+type ConnectionNumberFn = unsafe extern "C" fn(display: *mut c_void) -> c_int;
+type XcbFileDescriptorFn = unsafe extern "C" fn(connection: *mut c_void) -> c_int;
+
+/// libX11 中用到的函数
+struct XlibFunctions {
+    open_display: OpenDisplayFn,
+    close_display: CloseDisplayFn,
+    connection_number: ConnectionNumberFn,
+}
+
+lazy_static! {
+    /// 运行时加载 libX11，只在 XCB 显示连接需要走 Xlib 时使用
+    static ref XLIB: Option<XlibFunctions> = unsafe {
+        let library = dlopen(&b"libX11.so.6\0"[0] as *const u8 as *const _, RTLD_LAZY);
+        if library.is_null() {
+            return None;
+        }
+
+        let open_display = dlsym(library, &b"XOpenDisplay\0"[0] as *const u8 as *const _);
+        let close_display = dlsym(library, &b"XCloseDisplay\0"[0] as *const u8 as *const _);
+        let connection_number =
+            dlsym(library, &b"XConnectionNumber\0"[0] as *const u8 as *const _);
+        if open_display.is_null() || close_display.is_null() || connection_number.is_null() {
+            return None;
+        }
+
+        Some(XlibFunctions {
+            open_display: std::mem::transmute::<*mut c_void, OpenDisplayFn>(open_display),
+            close_display: std::mem::transmute::<*mut c_void, CloseDisplayFn>(close_display),
+            connection_number: std::mem::transmute::<*mut c_void, ConnectionNumberFn>(
+                connection_number,
+            ),
+        })
+    };
+
+    /// libxcb 的 xcb_get_file_descriptor；调用者持有 XCB 连接时 libxcb 已经加载
+    static ref XCB_GET_FILE_DESCRIPTOR: Option<XcbFileDescriptorFn> = unsafe {
+        let library = dlopen(&b"libxcb.so.1\0"[0] as *const u8 as *const _, RTLD_LAZY);
+        if library.is_null() {
+            return None;
+        }
+        let get_file_descriptor =
+            dlsym(library, &b"xcb_get_file_descriptor\0"[0] as *const u8 as *const _);
+        if get_file_descriptor.is_null() {
+            return None;
+        }
+        Some(std::mem::transmute::<*mut c_void, XcbFileDescriptorFn>(get_file_descriptor))
+    };
+}
+
+/// 套接字的对端地址（getpeername），用于判断两个连接是否连到同一个 X 服务器
+unsafe fn peer_address(fd: c_int) -> Option<Vec<u8>> {
+    let mut address: libc::sockaddr_storage = std::mem::zeroed();
+    let mut len = std::mem::size_of::<libc::sockaddr_storage>() as libc::socklen_t;
+    let result = libc::getpeername(
+        fd,
+        &mut address as *mut libc::sockaddr_storage as *mut libc::sockaddr,
+        &mut len,
+    );
+    if result != 0 {
+        return None;
+    }
+    let bytes = std::slice::from_raw_parts(&address as *const _ as *const u8, len as usize);
+    Some(bytes.to_vec())
+}
+
+/// 自己打开的 Xlib 显示连接，释放时关闭
+///
+/// 驱动不支持 EGL_EXT_platform_xcb 时，XCB 窗口通过它走 X11 平台：
+/// xcb_window_t 和 Xlib 的 Window 都是 XID，同一个 X 服务器上的不同连接可以共用。
+#[derive(Debug)]
+pub(crate) struct XlibDisplay(*mut c_void);
+
+impl XlibDisplay {
+    /// 连接 DISPLAY 环境变量指定的 X 服务器
+    pub(crate) fn open() -> Result<Self, InstanceError> {
+        let functions = match XLIB.as_ref() {
+            Some(functions) => functions,
+            None => return Err(InstanceError::LibraryLoadFailed("libX11.so.6")),
+        };
+
+        let display = unsafe { (functions.open_display)(std::ptr::null()) };
+        if display.is_null() {
+            return Err(InstanceError::IncompatibleWindowHandle);
+        }
+        Ok(Self(display))
+    }
+
+    /// 连接 `xcb_connection` 所在的 X 服务器
+    ///
+    /// Xlib 不能包装已有的 XCB 连接，只能按 DISPLAY 另开一个连接，再比较两个连接的对端地址；
+    /// 不是同一个服务器或无法确认时返回 `InstanceError::IncompatibleWindowHandle`。
+    pub(crate) fn open_for_xcb(xcb_connection: *mut c_void) -> Result<Self, InstanceError> {
+        let get_file_descriptor = match XCB_GET_FILE_DESCRIPTOR.as_ref() {
+            Some(get_file_descriptor) => get_file_descriptor,
+            None => return Err(InstanceError::LibraryLoadFailed("libxcb.so.1")),
+        };
+        let display = Self::open()?;
+        let same_server = unsafe {
+            // open 成功时 XLIB 一定已经加载
+            let functions = XLIB.as_ref().unwrap();
+            let xcb_peer = peer_address(get_file_descriptor(xcb_connection));
+            let xlib_peer = peer_address((functions.connection_number)(display.0));
+            xcb_peer.is_some() && xcb_peer == xlib_peer
+        };
+        if !same_server {
+            log::warn!("DISPLAY 指向的 X 服务器不是 XCB 连接所在的服务器");
+            return Err(InstanceError::IncompatibleWindowHandle);
+        }
+        Ok(display)
+    }
+
+    #[inline]
+    pub(crate) fn as_ptr(&self) -> *mut c_void {
+        self.0
+    }
+}
+
+impl Drop for XlibDisplay {
+    fn drop(&mut self) {
+        if let Some(functions) = XLIB.as_ref() {
+            unsafe { (functions.close_display)(self.0) };
+        }
+    }
+}
@@ -4,9 +4,11 @@ use std::sync::{
 };
 
 use super::{context::WebContext, surface::WebSurface};
-use crate::{InstanceError, PowerPreference};
+use crate::{
+    ConfigRequest, ContextOptions, FramePacing, InstanceError, PowerPreference, PresentMode,
+};
 use glow::HasContext;
-use raw_window_handle::{HasRawDisplayHandle, HasRawWindowHandle, RawWindowHandle};
+use raw_window_handle::{HasDisplayHandle, HasWindowHandle, RawWindowHandle};
 use wasm_bindgen::{JsCast, JsValue};
 use web_sys::js_sys;
 
@@ -24,8 +26,13 @@ impl WebInstance {
     /// # 参数
     /// - `power`: 表示GPU的功耗偏好。
     /// - `_is_vsync`: 是否启用垂直同步。
+    /// - `_config`: 帧缓冲配置，WebGL 下由浏览器决定，忽略。
     #[inline]
-    pub fn new(power: PowerPreference, _is_vsync: bool) -> Result<Self, InstanceError> {
+    pub fn new(
+        power: PowerPreference,
+        _is_vsync: bool,
+        _config: &ConfigRequest,
+    ) -> Result<Self, InstanceError> {
         Ok(WebInstance(None))
     }
 
@@ -34,16 +41,14 @@ impl WebInstance {
     /// # 参数
     /// - `window`: 包含原始窗口句柄的窗口。
     #[inline]
-    pub fn create_surface<W: HasRawWindowHandle + HasRawDisplayHandle>(
+    pub fn create_surface<W: HasWindowHandle + HasDisplayHandle>(
         &self,
         window: &W,
     ) -> Result<WebSurface, InstanceError> {
         // 获取窗口的原始句柄ID。
-        let canvas_attribute = if let Ok(RawWindowHandle::Web(handle)) = window.raw_window_handle()  
-        {
-            handle.id
-        } else {
-            return Err(InstanceError::IncompatibleWindowHandle);
+        let canvas_attribute = match window.window_handle().map(|handle| handle.as_raw()) {
+            Ok(RawWindowHandle::Web(handle)) => handle.id,
+            _ => return Err(InstanceError::IncompatibleWindowHandle),
         };
 
         // 获取全局window对象。
@@ -110,8 +115,19 @@ impl WebInstance {
     }
 
     /// 创建一个新的WebGL上下文。
+    ///
+    /// WebGL 没有共享组，`share` 不为 `None` 时返回 `InstanceError::RequiredExtensionUnavailable`。
+    /// WebGL 不支持调试输出，忽略 `_options`。
     #[inline]
-    pub fn create_context(&self) -> Result<WebContext, InstanceError> {
+    pub fn create_context(
+        &self,
+        share: Option<&WebContext>,
+        _options: &ContextOptions,
+    ) -> Result<WebContext, InstanceError> {
+        if share.is_some() {
+            return Err(InstanceError::RequiredExtensionUnavailable);
+        }
+
         // 创建一个新的canvas元素。
         let value: wasm_bindgen::JsValue = web_sys::window()
             .unwrap()
@@ -143,13 +159,13 @@ impl WebInstance {
         &'a mut self,
         surface: Option<&'a WebSurface>,
         context: Option<&WebContext>,
-    ) {
+    ) -> Result<(), InstanceError> {
         if let Some(context) = context {
             if let Some(surface) = surface {
                 // 如果已经绑定了正确的上下文和表面，则直接返回。
                 if let Some(bind_context) = &self.0 {
                     if bind_context == surface {
-                        return;
+                        return Ok(());
                     }
                 }
                 // 更新绑定的表面。
@@ -158,25 +174,37 @@ impl WebInstance {
                 // 如果未提供表面，则直接绑定上下文。
                 if let Some(bind_context) = &self.0 {
                     if bind_context == context {
-                        return;
+                        return Ok(());
                     }
                 }
                 self.0.replace(context.clone());
             }
         }
+        Ok(())
+    }
+
+    /// 设置表面的呈现模式；WebGL 的呈现时机由浏览器决定，总是返回 `Fifo`。
+    #[inline]
+    pub fn set_present_mode(
+        &self,
+        _surface: &WebSurface,
+        _mode: PresentMode,
+    ) -> Result<PresentMode, InstanceError> {
+        Ok(PresentMode::Fifo)
     }
 
-    /// 获取当前实例的Glow上下文引用。
+    /// 帧率由浏览器的 requestAnimationFrame 决定，返回 true 表示不做软件等待。
     #[inline]
-    pub fn get_glow<'a>(&'a self) -> &glow::Context {
-        self.0.as_ref().unwrap().context.as_ref()
+    pub fn set_frame_pacing(&self, _pacing: &FramePacing) -> bool {
+        true
     }
 
     /// 交换表面的缓冲区。
     #[inline]
-    pub fn swap_buffers(&self, surface: &WebSurface) {
+    pub fn swap_buffers(&self, surface: &WebSurface) -> Result<(), InstanceError> {
         // let c = self.0.as_ref().unwrap().context.as_ref();
         // let e = unsafe { c.get_error() };
         // log::error!("============= swap_buffers: {}", e);
+        Ok(())
     }
 }
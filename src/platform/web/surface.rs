@@ -6,6 +6,14 @@ pub struct WebSurface {
     pub id: u64,
 }
 
+impl WebSurface {
+    /// WebGL2 上下文的函数表
+    #[inline]
+    pub(crate) fn glow(&self) -> &glow::Context {
+        &self.context
+    }
+}
+
 unsafe impl Sync for WebSurface {}
 unsafe impl Send for WebSurface {}
 
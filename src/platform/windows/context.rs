@@ -1,12 +1,43 @@
 use std::ptr;
+use std::sync::OnceLock;
 
 use winapi::{
     shared::windef::HGLRC,
     um::wingdi::{wglDeleteContext, wglGetCurrentContext, wglMakeCurrent},
 };
 
-#[derive(Debug, Eq, PartialEq)]
-pub struct WglContext(pub u64);
+use super::util::get_proc_address;
+use crate::debug::install_debug_output;
+use crate::DebugFilter;
+
+/// WGL 上下文句柄、它的 GL 函数表（第一次绑定时加载）和调试消息的过滤条件
+#[derive(Debug)]
+pub struct WglContext(
+    pub u64,
+    pub(crate) OnceLock<glow::Context>,
+    pub(crate) Option<DebugFilter>,
+);
+
+impl WglContext {
+    /// GL 函数表；wglGetProcAddress 需要上下文已绑定，必须在绑定后第一次调用
+    pub(crate) fn glow(&self) -> &glow::Context {
+        self.1.get_or_init(|| {
+            let mut gl = unsafe { glow::Context::from_loader_function(get_proc_address) };
+            if let Some(filter) = &self.2 {
+                install_debug_output(&mut gl, filter.clone());
+            }
+            gl
+        })
+    }
+}
+
+impl PartialEq for WglContext {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl Eq for WglContext {}
 
 impl Drop for WglContext {
     #[inline]
@@ -1,4 +1,8 @@
 use std::os::raw::c_int;
+use std::sync::{
+    atomic::{AtomicI32, Ordering},
+    OnceLock,
+};
 
 use raw_window_handle::{HasDisplayHandle, HasWindowHandle, RawWindowHandle};
 use winapi::{
@@ -8,17 +12,20 @@ use winapi::{
     },
     um::{
         errhandlingapi::GetLastError,
-        wingdi::{self, wglMakeCurrent, SwapBuffers, PIXELFORMATDESCRIPTOR},
+        wingdi::{self, wglGetCurrentDC, wglMakeCurrent, SwapBuffers, PIXELFORMATDESCRIPTOR},
         winuser,
     },
 };
 
 use super::{
     context::WglContext,
-    surface::WglSurface,
-    util::{get_proc_address, set_exported_variables, HiddenWindow, WGL_EXTENSION_FUNCTIONS},
+    surface::{WglSurface, SWAP_INTERVAL_UNSET},
+    util::{set_exported_variables, HiddenWindow, WGL_EXTENSION_FUNCTIONS},
+};
+use crate::{
+    platform::windows::util::set_dc_pixel_format, ConfigRequest, ContextOptions, FramePacing,
+    InstanceError, PowerPreference, PresentMode,
 };
-use crate::{platform::windows::util::set_dc_pixel_format, InstanceError, PowerPreference};
 
 type GLenum = u32;
 
@@ -36,24 +43,22 @@ const WGL_TYPE_RGBA_ARB: GLenum = 0x202b;
 const WGL_CONTEXT_MAJOR_VERSION_ARB: GLenum = 0x2091;
 const WGL_CONTEXT_MINOR_VERSION_ARB: GLenum = 0x2092;
 const WGL_CONTEXT_PROFILE_MASK_ARB: GLenum = 0x9126;
+const WGL_CONTEXT_FLAGS_ARB: GLenum = 0x2094;
 const WGL_ALPHA_BITS_ARB: GLenum = 0x201b;
+const WGL_SAMPLE_BUFFERS_ARB: GLenum = 0x2041;
+const WGL_SAMPLES_ARB: GLenum = 0x2042;
 
 const WGL_CONTEXT_CORE_PROFILE_BIT_ARB: GLenum = 0x00000001;
+const WGL_CONTEXT_DEBUG_BIT_ARB: GLenum = 0x00000001;
 // const WGL_CONTEXT_COMPATIBILITY_PROFILE_BIT_ARB: GLenum = 0x00000002;
 
 #[derive(Debug)]
 pub struct WglInstance {
-    context: Option<glow::Context>, // 可选的OpenGL上下文（通过glow库管理）
-
     window_hwnd: HWND, // 隐藏窗口的句柄
     window_hdc: HDC, // 隐藏窗口的设备上下文句柄
 
-    is_vsync: bool, // 是否启用垂直同步
-
-    #[cfg(feature = "fps")]
-    fps: AtomicU32, // (条件编译): 帧率计数器
-    #[cfg(feature = "fps")]
-    time: pi_share::ShareCell<std::time::Instant>, // (条件编译): 用于计算FPS的时间戳
+    is_vsync: bool, // 新建表面是否使用垂直同步，之后可以用set_present_mode修改
+    config: ConfigRequest, // 像素格式的选择条件
 }
 
 impl Drop for WglInstance {
@@ -69,6 +74,7 @@ impl WglInstance {
     /// # 参数
     /// - `power`: 显卡性能偏好（如高性能或节能）
     /// - `is_vsync`: 是否启用垂直同步
+    /// - `config`: 像素格式的选择条件，在`create_context`时使用
     ///
     /// # 返回值
     /// - `Ok(Self)`: 成功创建的实例
@@ -77,7 +83,11 @@ impl WglInstance {
     /// # 注意
     /// 内部会创建一个隐藏窗口用于初始化OpenGL环境
     #[inline]
-    pub fn new(power: PowerPreference, is_vsync: bool) -> Result<Self, InstanceError> {
+    pub fn new(
+        power: PowerPreference,
+        is_vsync: bool,
+        config: &ConfigRequest,
+    ) -> Result<Self, InstanceError> {
         log::error!("new");
         set_exported_variables(power);
 
@@ -85,15 +95,11 @@ impl WglInstance {
         let window_hdc = unsafe { winuser::GetDC(window_hwnd) };
 
         Ok(WglInstance {
-            context: None,
             window_hwnd,
             window_hdc,
 
             is_vsync,
-            #[cfg(feature = "fps")]
-            fps: AtomicU32::new(0),
-            #[cfg(feature = "fps")]
-            time: pi_share::ShareCell::new(std::time::Instant::now()),
+            config: *config,
         })
     }
 
@@ -128,22 +134,36 @@ impl WglInstance {
         let pixel_format = unsafe { wingdi::GetPixelFormat(context_dc) };
         set_dc_pixel_format(real_dc, pixel_format);
 
-        Ok(WglSurface(real_dc as u64))
+        let interval = if self.is_vsync { SWAP_INTERVAL_UNSET } else { 0 };
+        Ok(WglSurface(real_dc as u64, AtomicI32::new(interval)))
     }
 
     /// 创建OpenGL 3.3核心配置文件上下文
     ///
+    /// # 参数
+    /// - `share`: 与之共享对象的上下文，为 `None` 时不共享
+    /// - `options`: 请求调试上下文时加上 WGL_CONTEXT_DEBUG_BIT_ARB
+    ///
     /// # 返回值
     /// - `Ok(WglContext)`: 成功创建的OpenGL上下文
     /// - `Err(InstanceError::RequiredExtensionUnavailable)`: 缺少必要WGL扩展
     /// - `Err(InstanceError::ContextCreationFailed)`: 上下文创建失败
     ///
     /// # 像素格式属性
-    /// 使用32位颜色、24位深度缓冲、8位模板缓冲、8位Alpha通道
+    /// 至少使用32位颜色、24位深度缓冲、8位模板缓冲、8位Alpha通道，
+    /// `ConfigRequest`要求更多时取请求值；排序策略由驱动决定
     #[allow(non_snake_case)]
-    pub fn create_context(&self) -> Result<WglContext, InstanceError> {
+    pub fn create_context(
+        &self,
+        share: Option<&WglContext>,
+        options: &ContextOptions,
+    ) -> Result<WglContext, InstanceError> {
         log::error!("create_context");
-        let pixel_format_attribs = [
+        let config = &self.config;
+        let color_bits =
+            (config.red_size as c_int + config.green_size as c_int + config.blue_size as c_int)
+                .max(32);
+        let mut pixel_format_attribs = vec![
             WGL_DRAW_TO_WINDOW_ARB as c_int,
             1 as c_int,
             WGL_SUPPORT_OPENGL_ARB as c_int,
@@ -155,15 +175,23 @@ impl WglInstance {
             WGL_PIXEL_TYPE_ARB as c_int,
             WGL_TYPE_RGBA_ARB as c_int,
             WGL_COLOR_BITS_ARB as c_int,
-            32,
+            color_bits,
             WGL_DEPTH_BITS_ARB as c_int,
-            24,
+            (config.depth_size as c_int).max(24),
             WGL_STENCIL_BITS_ARB as c_int,
-            8,
+            (config.stencil_size as c_int).max(8),
             WGL_ALPHA_BITS_ARB as c_int,
-            8,
-            0,
+            (config.alpha_size as c_int).max(8),
         ];
+        if config.samples > 0 {
+            pixel_format_attribs.extend_from_slice(&[
+                WGL_SAMPLE_BUFFERS_ARB as c_int,
+                1,
+                WGL_SAMPLES_ARB as c_int,
+                config.samples as c_int,
+            ]);
+        }
+        pixel_format_attribs.push(0);
         // int pixel_format;
         // UINT num_formats;
 
@@ -184,7 +212,9 @@ impl WglInstance {
                 &mut pixel_format_count,
             )
         };
-        assert_ne!(ok, FALSE);
+        if ok == FALSE || pixel_format_count == 0 {
+            return Err(last_error("wglChoosePixelFormatARB"));
+        }
 
         let mut pixel_format_descriptor = unsafe { std::mem::zeroed() };
         unsafe {
@@ -197,34 +227,46 @@ impl WglInstance {
         };
         let ok =
             unsafe { wingdi::SetPixelFormat(real_dc, pixel_format, &mut pixel_format_descriptor) };
-        // println!()
-        assert_ne!(ok, FALSE);
+        if ok == FALSE {
+            return Err(last_error("SetPixelFormat"));
+        }
 
         // Specify that we want to create an OpenGL 3.3 core profile context
-        let gl33_attribs = [
+        let mut gl33_attribs = vec![
             WGL_CONTEXT_MAJOR_VERSION_ARB as c_int,
             3,
             WGL_CONTEXT_MINOR_VERSION_ARB as c_int,
             3,
             WGL_CONTEXT_PROFILE_MASK_ARB as c_int,
             WGL_CONTEXT_CORE_PROFILE_BIT_ARB as c_int,
-            0,
         ];
+        if options.debug.is_some() {
+            gl33_attribs.extend_from_slice(&[
+                WGL_CONTEXT_FLAGS_ARB as c_int,
+                WGL_CONTEXT_DEBUG_BIT_ARB as c_int,
+            ]);
+        }
+        gl33_attribs.push(0);
 
         let wglCreateContextAttribsARB = match WGL_EXTENSION_FUNCTIONS.wglCreateContextAttribsARB {
             None => return Err(InstanceError::RequiredExtensionUnavailable),
             Some(ref func) => func,
         };
 
+        let share_context = share.map_or(std::ptr::null_mut(), |share| share.0 as HGLRC);
         let gl33_context = unsafe {
-            wglCreateContextAttribsARB(real_dc, std::ptr::null_mut(), gl33_attribs.as_ptr())
+            wglCreateContextAttribsARB(real_dc, share_context, gl33_attribs.as_ptr())
         };
 
         if gl33_context.is_null() {
             return Err(InstanceError::ContextCreationFailed);
         }
 
-        Ok(WglContext(gl33_context as u64))
+        Ok(WglContext(
+            gl33_context as u64,
+            OnceLock::new(),
+            options.debug.clone(),
+        ))
     }
 
     /// 设置当前渲染上下文和表面
@@ -233,73 +275,113 @@ impl WglInstance {
     /// - `surface`: 可选的要绑定的渲染表面
     /// - `context`: 可选的要激活的OpenGL上下文
     ///
-    /// # Panics
-    /// - 如果绑定操作失败会触发断言
-    ///
-    /// # 注意
-    /// 当首次绑定上下文时会初始化glow上下文
-    pub fn make_current(&mut self, surface: Option<&WglSurface>, context: Option<&WglContext>) {
+    /// # 错误
+    /// - 绑定失败时返回 `InstanceError::Win32CallFailed`
+    pub fn make_current(
+        &mut self,
+        surface: Option<&WglSurface>,
+        context: Option<&WglContext>,
+    ) -> Result<(), InstanceError> {
         if let Some(context) = context {
             if let Some(surface) = surface {
                 let ok = unsafe { wglMakeCurrent(surface.0 as HDC, context.0 as HGLRC) };
+                if ok == FALSE {
+                    return Err(last_error("wglMakeCurrent"));
+                }
                 // set_dc_pixel_format(dc, pixel_format)
-                if !self.is_vsync {
-                    if let Some(func) = WGL_EXTENSION_FUNCTIONS.wglSwapIntervalEXT {
-                        let ok = unsafe { func(0) };
-                        if ok == 0 {
-                            let err = unsafe { GetLastError() };
-                            println!("vsync closed failed!!! error code: {}", err);
-                        } else {
-                            // println!("vsync closed successfully!!!");
-                        }
-                    }
+                // 应用表面的呈现模式，失败不影响绑定
+                if let Err(err) = apply_swap_interval(surface) {
+                    log::warn!("设置交换间隔失败: {}", err);
                 }
-                assert_ne!(ok, FALSE);
             } else {
                 let ok = unsafe { wglMakeCurrent(self.window_hdc, context.0 as HGLRC) };
-                assert_ne!(ok, FALSE);
-            }
-            if self.context.is_none() {
-                let gl = unsafe {
-                    glow::Context::from_loader_function(|symbol_name| get_proc_address(symbol_name))
-                };
-                self.context.replace(gl);
+                if ok == FALSE {
+                    return Err(last_error("wglMakeCurrent"));
+                }
             }
         } else {
             let ok = unsafe { wglMakeCurrent(std::ptr::null_mut(), std::ptr::null_mut()) };
-            assert_ne!(ok, FALSE);
+            if ok == FALSE {
+                return Err(last_error("wglMakeCurrent"));
+            }
         }
+        Ok(())
     }
 
-    /// 获取当前glow OpenGL上下文
+    /// 设置表面的呈现模式
     ///
-    /// # Panics
-    /// 如果上下文尚未初始化（未调用过make_current）
+    /// 表面正绑定在当前线程时立即生效，否则在下一次绑定时生效；
+    /// 不支持 WGL_EXT_swap_control_tear 时 `AdaptiveFifo` 退回 `Fifo`，在设置前就按扩展字符串决定
+    ///
+    /// # 返回值
+    /// - 实际生效的呈现模式，表面没有绑定时也是；没有 wglSwapIntervalEXT 时总是 `Fifo`
+    pub fn set_present_mode(
+        &self,
+        surface: &WglSurface,
+        mode: PresentMode,
+    ) -> Result<PresentMode, InstanceError> {
+        if WGL_EXTENSION_FUNCTIONS.wglSwapIntervalEXT.is_none() {
+            return Ok(PresentMode::Fifo);
+        }
+
+        let mode = match mode {
+            PresentMode::AdaptiveFifo if !WGL_EXTENSION_FUNCTIONS.swap_control_tear => {
+                PresentMode::Fifo
+            }
+            mode => mode,
+        };
+        surface.1.store(mode.swap_interval(), Ordering::Relaxed);
+        if unsafe { wglGetCurrentDC() } == surface.0 as HDC {
+            apply_swap_interval(surface)?;
+        }
+        Ok(PresentMode::from_swap_interval(surface.1.load(Ordering::Relaxed)))
+    }
+
+    /// 由后端实现帧率限制；WGL 没有，返回 false，由软件等待实现
     #[inline]
-    pub fn get_glow<'a>(&'a self) -> &glow::Context {
-        self.context.as_ref().unwrap()
+    pub fn set_frame_pacing(&self, _pacing: &FramePacing) -> bool {
+        false
     }
 
     /// 交换前后缓冲区并处理垂直同步
     ///
     /// # 参数
     /// - `surface`: 要交换缓冲区的渲染表面
-    ///
-    /// # 功能
-    /// - 当启用`fps`特性时，会自动计算并打印帧率
     #[inline]
-    pub fn swap_buffers(&self, surface: &WglSurface) {
-        unsafe { SwapBuffers(surface.0 as HDC) };
-
-        #[cfg(feature = "fps")]
-        {
-            self.fps.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
-            let mut time = self.time.borrow_mut();
-            if time.elapsed().as_millis() > 1000 {
-                println!("PI_EGL FPS: {:?}", self.fps);
-                self.fps.store(0, std::sync::atomic::Ordering::Relaxed);
-                *time = std::time::Instant::now();
-            }
+    pub fn swap_buffers(&self, surface: &WglSurface) -> Result<(), InstanceError> {
+        let ok = unsafe { SwapBuffers(surface.0 as HDC) };
+
+        if ok == FALSE {
+            return Err(last_error("SwapBuffers"));
         }
+        Ok(())
     }
 }
+
+/// 对当前绑定的 `surface` 设置要求的交换间隔
+///
+/// 负的交换间隔需要 WGL_EXT_swap_control_tear，失败时退回 1 并记录到表面上
+fn apply_swap_interval(surface: &WglSurface) -> Result<(), InstanceError> {
+    let interval = surface.1.load(Ordering::Relaxed);
+    let func = match WGL_EXTENSION_FUNCTIONS.wglSwapIntervalEXT {
+        Some(func) if interval != SWAP_INTERVAL_UNSET => func,
+        _ => return Ok(()),
+    };
+
+    if unsafe { func(interval) } == FALSE {
+        if interval >= 0 {
+            return Err(last_error("wglSwapIntervalEXT"));
+        }
+        surface.1.store(1, Ordering::Relaxed);
+        if unsafe { func(1) } == FALSE {
+            return Err(last_error("wglSwapIntervalEXT"));
+        }
+    }
+    Ok(())
+}
+
+/// 取 GetLastError 的值，构造 `op` 失败的错误
+fn last_error(op: &'static str) -> InstanceError {
+    let code = unsafe { GetLastError() };
+    InstanceError::Win32CallFailed { op, code }
+}
@@ -1,5 +1,19 @@
-#[derive(Debug, Eq, PartialEq)]
-pub struct WglSurface(pub u64);
+use std::sync::atomic::AtomicI32;
+
+/// 没有要求交换间隔，使用驱动的默认值
+pub(crate) const SWAP_INTERVAL_UNSET: i32 = i32::MIN;
+
+/// 窗口的 HDC 和要求的交换间隔（呈现模式）
+#[derive(Debug)]
+pub struct WglSurface(pub u64, pub(crate) AtomicI32);
+
+impl PartialEq for WglSurface {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl Eq for WglSurface {}
 
 impl Drop for WglSurface {
     #[inline]
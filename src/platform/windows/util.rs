@@ -1,8 +1,8 @@
 use std::{
-    ffi::CString,
+    ffi::{CStr, CString},
     marker::PhantomData,
     mem,
-    os::raw::{c_int, c_void},
+    os::raw::{c_char, c_int, c_void},
     ptr,
     sync::mpsc::{self, Sender},
     thread,
@@ -80,6 +80,29 @@ pub(crate) struct WGLExtensionFunctions {
 
     /// wglSwapIntervalEXT函数用于控制交换区间，以实现垂直同步。
     pub wglSwapIntervalEXT: Option<unsafe extern "C" fn(interval: c_int) -> BOOL>,
+
+    /// 是否支持 WGL_EXT_swap_control_tear（负的交换间隔，即 `PresentMode::AdaptiveFifo`）
+    pub swap_control_tear: bool,
+}
+
+/// WGL 扩展字符串，需要已绑定上下文；查询函数都不可用时返回空字符串
+unsafe fn wgl_extensions(dc: HDC) -> String {
+    type GetExtensionsStringARB = unsafe extern "C" fn(hdc: HDC) -> *const c_char;
+    type GetExtensionsStringEXT = unsafe extern "C" fn() -> *const c_char;
+
+    let arb = wglGetProcAddress(&b"wglGetExtensionsStringARB\0"[0] as *const u8 as LPCSTR);
+    let ext = wglGetProcAddress(&b"wglGetExtensionsStringEXT\0"[0] as *const u8 as LPCSTR);
+    let extensions = if !arb.is_null() {
+        mem::transmute::<_, GetExtensionsStringARB>(arb)(dc)
+    } else if !ext.is_null() {
+        mem::transmute::<_, GetExtensionsStringEXT>(ext)()
+    } else {
+        ptr::null()
+    };
+    if extensions.is_null() {
+        return String::new();
+    }
+    CStr::from_ptr(extensions).to_string_lossy().into_owned()
 }
 
 /// extension_loader_thread函数负责加载WGL扩展函数。
@@ -214,6 +237,10 @@ extern "system" fn extension_loader_window_proc(
                     (*wgl_extension_functions).wglSwapIntervalEXT = Some(mem::transmute(func));
                 }
 
+                (*wgl_extension_functions).swap_control_tear = wgl_extensions(dc)
+                    .split_whitespace()
+                    .any(|e| e == "WGL_EXT_swap_control_tear");
+
                 // 清理上下文。
                 wglMakeCurrent(dc, std::ptr::null_mut());
                 wglDeleteContext(gl_context);
@@ -0,0 +1,476 @@
+use std::io::{self, Write};
+
+use glow::HasContext;
+
+use crate::InstanceError;
+
+/// 读取的矩形区域，GL 坐标：原点在左下角，单位像素
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PixelRect {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl PixelRect {
+    /// 从原点开始的 `width` x `height` 区域，通常是整个表面
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            x: 0,
+            y: 0,
+            width,
+            height,
+        }
+    }
+}
+
+/// `ImageData` 的像素格式，每个通道 8 位
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PixelFormat {
+    Rgba8,
+    Rgb8,
+    /// GL_EXT_read_format_bgra，常见于桌面驱动
+    Bgra8,
+}
+
+impl PixelFormat {
+    /// 每个像素的字节数
+    #[inline]
+    pub fn bytes_per_pixel(self) -> usize {
+        match self {
+            PixelFormat::Rgb8 => 3,
+            PixelFormat::Rgba8 | PixelFormat::Bgra8 => 4,
+        }
+    }
+}
+
+/// `Instance::read_pixels` 读取的格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ReadFormat {
+    /// 由 GL 转换为 RGBA8，所有帧缓冲都支持
+    Rgba8,
+    /// 驱动首选的格式（GL_IMPLEMENTATION_COLOR_READ_FORMAT / TYPE），省掉驱动内的转换；
+    /// 首选格式不是 `PixelFormat` 之一时退回 RGBA8
+    Native,
+}
+
+/// CPU 上的图像，行从上到下排列，行间没有填充
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImageData {
+    pub width: u32,
+    pub height: u32,
+    pub format: PixelFormat,
+    pub data: Vec<u8>,
+}
+
+impl ImageData {
+    /// 每行的字节数
+    #[inline]
+    pub fn stride(&self) -> usize {
+        self.width as usize * self.format.bytes_per_pixel()
+    }
+
+    /// 上下翻转，GL 读出的行是从下到上的
+    pub fn flip_vertical(&mut self) {
+        let stride = self.stride();
+        let height = self.height as usize;
+        for row in 0..height / 2 {
+            let (top, bottom) = self.data.split_at_mut((height - 1 - row) * stride);
+            top[row * stride..(row + 1) * stride].swap_with_slice(&mut bottom[..stride]);
+        }
+    }
+
+    /// 转换为 RGBA8，RGB 的 alpha 填 255
+    pub fn into_rgba8(self) -> ImageData {
+        let data = match self.format {
+            PixelFormat::Rgba8 => return self,
+            PixelFormat::Rgb8 => self
+                .data
+                .chunks_exact(3)
+                .flat_map(|p| [p[0], p[1], p[2], 255])
+                .collect(),
+            PixelFormat::Bgra8 => self
+                .data
+                .chunks_exact(4)
+                .flat_map(|p| [p[2], p[1], p[0], p[3]])
+                .collect(),
+        };
+        ImageData {
+            format: PixelFormat::Rgba8,
+            data,
+            ..self
+        }
+    }
+
+    /// 写出二进制 PPM（P6），丢弃 alpha
+    pub fn write_ppm<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        write!(writer, "P6\n{} {}\n255\n", self.width, self.height)?;
+        let bytes_per_pixel = self.format.bytes_per_pixel();
+        let mut row = Vec::with_capacity(self.width as usize * 3);
+        for line in self.data.chunks_exact(self.stride().max(1)) {
+            row.clear();
+            for p in line.chunks_exact(bytes_per_pixel) {
+                match self.format {
+                    PixelFormat::Bgra8 => row.extend_from_slice(&[p[2], p[1], p[0]]),
+                    PixelFormat::Rgba8 | PixelFormat::Rgb8 => row.extend_from_slice(&p[..3]),
+                }
+            }
+            writer.write_all(&row)?;
+        }
+        Ok(())
+    }
+
+    /// 写出 PNG（RGBA8 或 RGB8）
+    ///
+    /// 不压缩（deflate 的 stored 块），只用于截图和测试，文件大小约等于像素数据。
+    pub fn write_png<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        let image = match self.format {
+            PixelFormat::Bgra8 => self.clone().into_rgba8(),
+            PixelFormat::Rgba8 | PixelFormat::Rgb8 => self.clone(),
+        };
+        let color_type = match image.format {
+            PixelFormat::Rgb8 => 2,
+            _ => 6,
+        };
+
+        writer.write_all(b"\x89PNG\r\n\x1a\n")?;
+
+        let mut header = Vec::with_capacity(13);
+        header.extend_from_slice(&image.width.to_be_bytes());
+        header.extend_from_slice(&image.height.to_be_bytes());
+        // 位深 8，无压缩方式 / 滤波方式 / 隔行扫描的变化
+        header.extend_from_slice(&[8, color_type, 0, 0, 0]);
+        write_png_chunk(&mut writer, b"IHDR", &header)?;
+
+        // 每行前加滤波类型 0（None）
+        let stride = image.stride();
+        let mut raw = Vec::with_capacity((stride + 1) * image.height as usize);
+        for line in image.data.chunks_exact(stride.max(1)) {
+            raw.push(0);
+            raw.extend_from_slice(line);
+        }
+        write_png_chunk(&mut writer, b"IDAT", &zlib_stored(&raw))?;
+        write_png_chunk(&mut writer, b"IEND", &[])
+    }
+}
+
+/// 读取当前绑定到 GL_READ_FRAMEBUFFER 的帧缓冲，结果的行从上到下排列
+///
+/// 读取时 GL_PACK_ALIGNMENT 临时设为 1；调用者需要保证没有绑定 GL_PIXEL_PACK_BUFFER。
+pub(crate) fn read_pixels(
+    gl: &glow::Context,
+    rect: PixelRect,
+    format: ReadFormat,
+) -> Result<ImageData, InstanceError> {
+    let (gl_format, pixel_format) = read_format(gl, format);
+    let mut image = ImageData {
+        width: rect.width,
+        height: rect.height,
+        format: pixel_format,
+        data: vec![0; rect.width as usize * rect.height as usize * pixel_format.bytes_per_pixel()],
+    };
+    read_into(
+        gl,
+        rect,
+        gl_format,
+        glow::PixelPackData::Slice(&mut image.data),
+    )?;
+    image.flip_vertical();
+    Ok(image)
+}
+
+/// glReadPixels，GL_PACK_ALIGNMENT 临时设为 1
+pub(crate) fn read_into(
+    gl: &glow::Context,
+    rect: PixelRect,
+    gl_format: u32,
+    pixels: glow::PixelPackData,
+) -> Result<(), InstanceError> {
+    let code = unsafe {
+        // 清掉之前的错误；上下文丢失时可能一直返回错误，限制次数
+        for _ in 0..16 {
+            if gl.get_error() == glow::NO_ERROR {
+                break;
+            }
+        }
+
+        let alignment = gl.get_parameter_i32(glow::PACK_ALIGNMENT);
+        gl.pixel_store_i32(glow::PACK_ALIGNMENT, 1);
+        gl.read_pixels(
+            rect.x,
+            rect.y,
+            rect.width as i32,
+            rect.height as i32,
+            gl_format,
+            glow::UNSIGNED_BYTE,
+            pixels,
+        );
+        let code = gl.get_error();
+        gl.pixel_store_i32(glow::PACK_ALIGNMENT, alignment);
+        code
+    };
+    if code != glow::NO_ERROR {
+        return Err(InstanceError::GlCallFailed {
+            op: "glReadPixels",
+            code,
+        });
+    }
+    Ok(())
+}
+
+/// 读取时使用的 GL 格式和结果的像素格式
+pub(crate) fn read_format(gl: &glow::Context, format: ReadFormat) -> (u32, PixelFormat) {
+    match format {
+        ReadFormat::Rgba8 => (glow::RGBA, PixelFormat::Rgba8),
+        ReadFormat::Native => native_read_format(gl),
+    }
+}
+
+/// 驱动首选的读取格式，不是 8 位 RGBA / RGB / BGRA 时退回 RGBA8
+fn native_read_format(gl: &glow::Context) -> (u32, PixelFormat) {
+    const BGRA_EXT: u32 = 0x80E1;
+
+    let (format, ty) = unsafe {
+        (
+            gl.get_parameter_i32(glow::IMPLEMENTATION_COLOR_READ_FORMAT) as u32,
+            gl.get_parameter_i32(glow::IMPLEMENTATION_COLOR_READ_TYPE) as u32,
+        )
+    };
+    match (format, ty) {
+        (glow::RGB, glow::UNSIGNED_BYTE) => (glow::RGB, PixelFormat::Rgb8),
+        (BGRA_EXT, glow::UNSIGNED_BYTE) => (BGRA_EXT, PixelFormat::Bgra8),
+        _ => (glow::RGBA, PixelFormat::Rgba8),
+    }
+}
+
+/// 写一个 PNG 块：长度、类型、数据、CRC
+fn write_png_chunk<W: Write>(writer: &mut W, kind: &[u8; 4], data: &[u8]) -> io::Result<()> {
+    writer.write_all(&(data.len() as u32).to_be_bytes())?;
+    writer.write_all(kind)?;
+    writer.write_all(data)?;
+    let crc = crc32(crc32(0xFFFF_FFFF, kind), data) ^ 0xFFFF_FFFF;
+    writer.write_all(&crc.to_be_bytes())
+}
+
+/// zlib 流，deflate 全部使用 stored 块（每块最多 65535 字节）
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(data.len() + data.len() / 65535 * 5 + 11);
+    out.extend_from_slice(&[0x78, 0x01]);
+    let mut blocks = data.chunks(65535).peekable();
+    if blocks.peek().is_none() {
+        // 空数据也需要一个结束块
+        out.extend_from_slice(&[1, 0, 0, 0xFF, 0xFF]);
+    }
+    while let Some(block) = blocks.next() {
+        let last = blocks.peek().is_none() as u8;
+        let len = block.len() as u16;
+        out.push(last);
+        out.extend_from_slice(&len.to_le_bytes());
+        out.extend_from_slice(&(!len).to_le_bytes());
+        out.extend_from_slice(block);
+    }
+    out.extend_from_slice(&adler32(data).to_be_bytes());
+    out
+}
+
+/// CRC-32（PNG / zlib 使用的多项式 0xEDB88320），`crc` 为上一段的结果，不做最终取反
+fn crc32(mut crc: u32, data: &[u8]) -> u32 {
+    for byte in data {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    crc
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for chunk in data.chunks(5552) {
+        for byte in chunk {
+            a += *byte as u32;
+            b += a;
+        }
+        a %= 65521;
+        b %= 65521;
+    }
+    (b << 16) | a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn image(width: u32, height: u32, format: PixelFormat) -> ImageData {
+        let len = width as usize * height as usize * format.bytes_per_pixel();
+        ImageData {
+            width,
+            height,
+            format,
+            data: (0..len).map(|i| (i * 7 % 251) as u8).collect(),
+        }
+    }
+
+    /// 解析 write_png 的输出（只支持 stored 块），检查 CRC 和 Adler-32，返回 IHDR 和去掉滤波字节的像素
+    fn decode_png(png: &[u8]) -> (Vec<u8>, Vec<u8>) {
+        assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+        let (mut pos, mut header, mut idat) = (8, Vec::new(), Vec::new());
+        let mut kinds = Vec::new();
+        while pos < png.len() {
+            let len = u32::from_be_bytes(png[pos..pos + 4].try_into().unwrap()) as usize;
+            let kind = &png[pos + 4..pos + 8];
+            let data = &png[pos + 8..pos + 8 + len];
+            let crc = u32::from_be_bytes(png[pos + 8 + len..pos + 12 + len].try_into().unwrap());
+            assert_eq!(crc32(crc32(0xFFFF_FFFF, kind), data) ^ 0xFFFF_FFFF, crc);
+            match kind {
+                b"IHDR" => header = data.to_vec(),
+                b"IDAT" => idat.extend_from_slice(data),
+                _ => {}
+            }
+            kinds.push(kind.to_vec());
+            pos += 12 + len;
+        }
+        assert_eq!(kinds.last().unwrap(), b"IEND");
+
+        assert_eq!(&idat[..2], &[0x78, 0x01]);
+        let (mut pos, mut raw) = (2, Vec::new());
+        loop {
+            let last = idat[pos];
+            let len = u16::from_le_bytes([idat[pos + 1], idat[pos + 2]]);
+            let nlen = u16::from_le_bytes([idat[pos + 3], idat[pos + 4]]);
+            assert_eq!(len, !nlen);
+            raw.extend_from_slice(&idat[pos + 5..pos + 5 + len as usize]);
+            pos += 5 + len as usize;
+            if last == 1 {
+                break;
+            }
+            assert_eq!(last, 0);
+        }
+        let adler = u32::from_be_bytes(idat[pos..pos + 4].try_into().unwrap());
+        assert_eq!(adler32(&raw), adler);
+        assert_eq!(pos + 4, idat.len());
+        (header, raw)
+    }
+
+    #[test]
+    fn crc32_matches_png() {
+        // 每个 PNG 文件结尾的 IEND 块的 CRC
+        assert_eq!(crc32(0xFFFF_FFFF, b"IEND") ^ 0xFFFF_FFFF, 0xAE42_6082);
+        assert_eq!(crc32(0xFFFF_FFFF, b"") ^ 0xFFFF_FFFF, 0);
+    }
+
+    #[test]
+    fn adler32_matches_zlib() {
+        assert_eq!(adler32(b"Wikipedia"), 0x11E6_0398);
+        assert_eq!(adler32(b""), 1);
+        // 超过 5552 字节时分段取模
+        assert_eq!(adler32(&[0xFF; 6000]), 0xA497_59EA);
+    }
+
+    #[test]
+    fn zlib_stored_splits_blocks() {
+        let data: Vec<u8> = (0..65535 + 10).map(|i| i as u8).collect();
+        let zlib = zlib_stored(&data);
+        // 头 2 字节，两个块各 5 字节的块头，Adler-32 4 字节
+        assert_eq!(zlib.len(), data.len() + 2 + 5 * 2 + 4);
+        assert_eq!(&zlib[2..7], &[0, 0xFF, 0xFF, 0, 0]);
+        assert_eq!(&zlib[65535 + 7..65535 + 12], &[1, 10, 0, 0xF5, 0xFF]);
+
+        assert_eq!(
+            zlib_stored(&[]),
+            [0x78, 0x01, 1, 0, 0, 0xFF, 0xFF, 0, 0, 0, 1]
+        );
+    }
+
+    #[test]
+    fn png_round_trip() {
+        // 每行 801 字节（含滤波字节），共 80100 字节，跨过 65535 字节的块边界
+        for format in [PixelFormat::Rgba8, PixelFormat::Rgb8] {
+            let image = image(200, 100, format);
+            let mut png = Vec::new();
+            image.write_png(&mut png).unwrap();
+
+            let (header, raw) = decode_png(&png);
+            assert_eq!(&header[..8], &[0, 0, 0, 200, 0, 0, 0, 100]);
+            let color_type = if format == PixelFormat::Rgb8 { 2 } else { 6 };
+            assert_eq!(&header[8..], &[8, color_type, 0, 0, 0]);
+
+            let stride = image.stride();
+            assert_eq!(raw.len(), (stride + 1) * 100);
+            for (row, line) in raw.chunks_exact(stride + 1).enumerate() {
+                assert_eq!(line[0], 0);
+                assert_eq!(&line[1..], &image.data[row * stride..(row + 1) * stride]);
+            }
+        }
+    }
+
+    #[test]
+    fn png_writes_bgra_as_rgba() {
+        let image = ImageData {
+            width: 1,
+            height: 1,
+            format: PixelFormat::Bgra8,
+            data: vec![1, 2, 3, 4],
+        };
+        let mut png = Vec::new();
+        image.write_png(&mut png).unwrap();
+        let (header, raw) = decode_png(&png);
+        assert_eq!(header[9], 6);
+        assert_eq!(raw, [0, 3, 2, 1, 4]);
+    }
+
+    #[test]
+    fn ppm_drops_alpha() {
+        let image = ImageData {
+            width: 2,
+            height: 1,
+            format: PixelFormat::Bgra8,
+            data: vec![1, 2, 3, 4, 5, 6, 7, 8],
+        };
+        let mut ppm = Vec::new();
+        image.write_ppm(&mut ppm).unwrap();
+        assert_eq!(ppm, b"P6\n2 1\n255\n\x03\x02\x01\x07\x06\x05");
+    }
+
+    #[test]
+    fn flip_vertical_odd_height() {
+        let mut image = image(2, 3, PixelFormat::Rgb8);
+        let rows: Vec<Vec<u8>> = image.data.chunks(6).map(|row| row.to_vec()).collect();
+        image.flip_vertical();
+        assert_eq!(
+            image.data,
+            [rows[2].clone(), rows[1].clone(), rows[0].clone()].concat()
+        );
+
+        let mut single = self::image(3, 1, PixelFormat::Rgba8);
+        let data = single.data.clone();
+        single.flip_vertical();
+        assert_eq!(single.data, data);
+    }
+
+    #[test]
+    fn into_rgba8_converts() {
+        let bgra = ImageData {
+            width: 2,
+            height: 1,
+            format: PixelFormat::Bgra8,
+            data: vec![1, 2, 3, 4, 5, 6, 7, 8],
+        };
+        let rgba = bgra.into_rgba8();
+        assert_eq!(rgba.format, PixelFormat::Rgba8);
+        assert_eq!(rgba.data, [3, 2, 1, 4, 7, 6, 5, 8]);
+
+        let rgb = ImageData {
+            width: 1,
+            height: 1,
+            format: PixelFormat::Rgb8,
+            data: vec![1, 2, 3],
+        };
+        assert_eq!(rgb.into_rgba8().data, [1, 2, 3, 255]);
+    }
+}
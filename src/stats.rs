@@ -0,0 +1,218 @@
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+/// 帧时间直方图的桶数，和 SwappyStats 的 MAX_FRAME_BUCKETS 一致
+pub const FRAME_BUCKETS: usize = 6;
+
+/// 滑动窗口保留的帧数
+pub const FRAME_WINDOW: usize = 240;
+
+/// 默认的帧预算，60 FPS
+pub const DEFAULT_FRAME_BUDGET: Duration = Duration::from_nanos(1_000_000_000 / 60);
+
+/// 帧统计，由 `Instance::frame_stats` 返回，仿照 SwappyStats
+///
+/// 帧时间是相邻两次 `swap_buffers` 的间隔，除 `total_frames` 外都只统计最近 `FRAME_WINDOW` 帧。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct FrameStats {
+    /// swap_buffers 成功的总次数
+    pub total_frames: u64,
+    /// 窗口内的帧数
+    pub window_frames: u32,
+    pub average: Duration,
+    pub min: Duration,
+    pub max: Duration,
+    pub p50: Duration,
+    pub p95: Duration,
+    pub p99: Duration,
+    /// 帧时间直方图，以帧预算为单位
+    ///
+    /// 例如：帧时间在 2 到 3 个帧预算之间的帧计入 `histogram[2]`；最后一个桶包含更长的帧。
+    pub histogram: [u64; FRAME_BUCKETS],
+    /// 卡顿帧数：帧时间超过中位数的 2 倍，并且超过帧预算
+    pub hitches: u32,
+    /// 统计使用的帧预算
+    pub budget: Duration,
+}
+
+/// 记录每帧的时间
+#[derive(Debug)]
+pub(crate) struct FrameStatsRecorder {
+    total_frames: u64,
+    last_swap: Option<Instant>,
+    frame_times: VecDeque<Duration>,
+    budget: Duration,
+}
+
+impl FrameStatsRecorder {
+    pub(crate) fn new() -> Self {
+        Self {
+            total_frames: 0,
+            last_swap: None,
+            frame_times: VecDeque::with_capacity(FRAME_WINDOW),
+            budget: DEFAULT_FRAME_BUDGET,
+        }
+    }
+
+    /// swap_buffers 成功后调用；wasm32 下 swap_buffers 不记录
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn record(&mut self, now: Instant) {
+        self.total_frames += 1;
+        if let Some(last_swap) = self.last_swap.replace(now) {
+            if self.frame_times.len() == FRAME_WINDOW {
+                self.frame_times.pop_front();
+            }
+            self.frame_times.push_back(now.saturating_duration_since(last_swap));
+        }
+    }
+
+    /// 清空窗口和总帧数
+    pub(crate) fn reset(&mut self) {
+        self.total_frames = 0;
+        self.last_swap = None;
+        self.frame_times.clear();
+    }
+
+    pub(crate) fn set_budget(&mut self, budget: Duration) {
+        self.budget = budget;
+    }
+
+    pub(crate) fn stats(&self) -> FrameStats {
+        let mut stats = FrameStats {
+            total_frames: self.total_frames,
+            budget: self.budget,
+            ..Default::default()
+        };
+        if self.frame_times.is_empty() {
+            return stats;
+        }
+
+        let mut sorted: Vec<Duration> = self.frame_times.iter().copied().collect();
+        sorted.sort_unstable();
+        // 最近秩法取百分位
+        let percentile = |p: usize| sorted[((sorted.len() * p).div_ceil(100)).max(1) - 1];
+
+        stats.window_frames = sorted.len() as u32;
+        stats.average = sorted.iter().sum::<Duration>() / sorted.len() as u32;
+        stats.min = sorted[0];
+        stats.max = sorted[sorted.len() - 1];
+        stats.p50 = percentile(50);
+        stats.p95 = percentile(95);
+        stats.p99 = percentile(99);
+
+        let budget = self.budget.as_nanos().max(1);
+        let hitch = (stats.p50 * 2).max(self.budget);
+        for frame_time in &sorted {
+            let bucket = (frame_time.as_nanos() / budget) as usize;
+            stats.histogram[bucket.min(FRAME_BUCKETS - 1)] += 1;
+            if *frame_time > hitch {
+                stats.hitches += 1;
+            }
+        }
+        stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MS: Duration = Duration::from_millis(1);
+
+    /// 依次以 `frame_times` 为间隔调用 record，第一次 record 只记录起点
+    fn recorder(
+        budget: Duration,
+        frame_times: impl IntoIterator<Item = Duration>,
+    ) -> FrameStatsRecorder {
+        let mut recorder = FrameStatsRecorder::new();
+        recorder.set_budget(budget);
+        let mut now = Instant::now();
+        recorder.record(now);
+        for frame_time in frame_times {
+            now += frame_time;
+            recorder.record(now);
+        }
+        recorder
+    }
+
+    #[test]
+    fn empty_window() {
+        let stats = recorder(DEFAULT_FRAME_BUDGET, []).stats();
+        assert_eq!(stats.total_frames, 1);
+        assert_eq!(stats.window_frames, 0);
+        assert_eq!(stats.p50, Duration::ZERO);
+        assert_eq!(stats.budget, DEFAULT_FRAME_BUDGET);
+    }
+
+    #[test]
+    fn percentiles() {
+        // 1..=100 毫秒，打乱顺序
+        let stats = recorder(10 * MS, (1..=100).map(|i| (i * 37 % 100 + 1) * MS)).stats();
+        assert_eq!(stats.window_frames, 100);
+        assert_eq!(stats.min, MS);
+        assert_eq!(stats.max, 100 * MS);
+        assert_eq!(stats.average, Duration::from_micros(50_500));
+        assert_eq!(stats.p50, 50 * MS);
+        assert_eq!(stats.p95, 95 * MS);
+        assert_eq!(stats.p99, 99 * MS);
+    }
+
+    #[test]
+    fn percentiles_of_few_frames() {
+        let stats = recorder(10 * MS, [3 * MS, MS]).stats();
+        assert_eq!(stats.p50, MS);
+        assert_eq!(stats.p99, 3 * MS);
+    }
+
+    #[test]
+    fn histogram() {
+        let stats = recorder(10 * MS, (1..=100).map(|i| i * MS)).stats();
+        // 第 i 个桶是 [i, i + 1) 个帧预算，最后一个桶包含更长的帧
+        assert_eq!(stats.histogram, [9, 10, 10, 10, 10, 51]);
+    }
+
+    #[test]
+    fn hitches() {
+        // 中位数 16 毫秒：超过 32 毫秒并且超过预算的帧算卡顿
+        let frames = [16 * MS; 20].into_iter().chain([30 * MS, 33 * MS, 50 * MS]);
+        let stats = recorder(DEFAULT_FRAME_BUDGET, frames).stats();
+        assert_eq!(stats.p50, 16 * MS);
+        assert_eq!(stats.hitches, 2);
+
+        // 帧时间都很短时，不超过帧预算的不算卡顿
+        let frames = [MS; 20].into_iter().chain([5 * MS]);
+        assert_eq!(recorder(DEFAULT_FRAME_BUDGET, frames).stats().hitches, 0);
+    }
+
+    #[test]
+    fn window_rollover() {
+        // 前 10 帧 100 毫秒，之后 FRAME_WINDOW 帧 10 毫秒，前 10 帧被挤出窗口
+        let frames = [100 * MS; 10].into_iter().chain([10 * MS; FRAME_WINDOW]);
+        let stats = recorder(DEFAULT_FRAME_BUDGET, frames).stats();
+        assert_eq!(stats.total_frames, FRAME_WINDOW as u64 + 11);
+        assert_eq!(stats.window_frames, FRAME_WINDOW as u32);
+        assert_eq!(stats.max, 10 * MS);
+        assert_eq!(stats.hitches, 0);
+
+        // 还差一帧挤出时，最后一个 100 毫秒的帧仍在窗口里
+        let frames = [100 * MS; 10]
+            .into_iter()
+            .chain([10 * MS; FRAME_WINDOW - 1]);
+        assert_eq!(recorder(DEFAULT_FRAME_BUDGET, frames).stats().max, 100 * MS);
+    }
+
+    #[test]
+    fn reset() {
+        let mut recorder = recorder(DEFAULT_FRAME_BUDGET, [10 * MS; 5]);
+        recorder.reset();
+        let stats = recorder.stats();
+        assert_eq!(stats.total_frames, 0);
+        assert_eq!(stats.window_frames, 0);
+
+        // reset 之后的第一次 record 只记录起点
+        recorder.record(Instant::now());
+        assert_eq!(recorder.stats().window_frames, 0);
+    }
+}
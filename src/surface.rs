@@ -16,3 +16,60 @@ pub struct Surface {
 
 unsafe impl Sync for Surface {}
 unsafe impl Send for Surface {}
+
+impl Surface {
+    // 窗口大小变化时调用，下一次 swap_buffers 时生效
+    // 只有 Wayland 窗口需要，其他平台的窗口表面跟随窗口大小，空实现
+    #[inline]
+    pub fn resize(&self, width: u32, height: u32) {
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        self.surface.resize(width, height);
+        #[cfg(not(any(target_os = "linux", target_os = "android")))]
+        let _ = (width, height);
+    }
+}
+
+/// 呈现模式，对应交换间隔（eglSwapInterval / wglSwapIntervalEXT）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PresentMode {
+    /// 交换间隔 1，等待垂直同步
+    Fifo,
+    /// 交换间隔 0，不等待垂直同步，可能撕裂
+    Immediate,
+    /// 交换间隔 -1，赶上垂直同步时等待，晚了立即交换
+    AdaptiveFifo,
+}
+
+impl PresentMode {
+    /// 对应的交换间隔
+    #[inline]
+    pub fn swap_interval(self) -> i32 {
+        match self {
+            PresentMode::Fifo => 1,
+            PresentMode::Immediate => 0,
+            PresentMode::AdaptiveFifo => -1,
+        }
+    }
+
+    /// 由交换间隔得到呈现模式，大于 1 的间隔也视为 Fifo
+    #[inline]
+    pub fn from_swap_interval(interval: i32) -> Self {
+        match interval {
+            0 => PresentMode::Immediate,
+            i if i < 0 => PresentMode::AdaptiveFifo,
+            _ => PresentMode::Fifo,
+        }
+    }
+}
+
+/// pbuffer 表面可绑定为纹理时的格式（EGL_TEXTURE_FORMAT）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum PbufferFormat {
+    /// 不绑定为纹理，只作为离屏绘制目标
+    #[default]
+    NoTexture,
+    /// 可以用 eglBindTexImage 绑定为 RGB 纹理
+    Rgb,
+    /// 可以用 eglBindTexImage 绑定为 RGBA 纹理
+    Rgba,
+}